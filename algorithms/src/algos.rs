@@ -0,0 +1,366 @@
+use utility::CommFlags;
+use std::mem::swap;
+
+use crate::faults::Byzantine;
+use crate::node_utils::{Link, NodeContext, NodeError, RelativePos};
+use crate::transport::Wire;
+use crate::{SortAlgorithm, Value};
+use utility::network::{comparators, odd_even_pairs_right, quiet_window, triplet_role, Comparator};
+use utility::{get_rounds, log, Algo, PartialOrder};
+
+#[derive(PartialEq, Debug)]
+pub struct OddEven;
+
+
+fn should_swap_right (partial_order:PartialOrder, cur_num:i32, rec_val:i32) -> bool{
+    (partial_order == PartialOrder::LessThan && 
+        cur_num > rec_val) || 
+    (partial_order == PartialOrder::GreaterThan &&
+        cur_num < rec_val)   
+}
+
+fn should_swap_left (partial_order:PartialOrder, cur_num:i32, rec_val:i32) -> bool{
+    (partial_order == PartialOrder::LessThan && 
+        cur_num < rec_val) || 
+    (partial_order == PartialOrder::GreaterThan &&
+        cur_num > rec_val)   
+}
+
+impl OddEven {
+
+    // Exchange : i32
+    fn receive_val(link:&mut Link) -> Result<i32, NodeError>{
+        link.recv(CommFlags::Exchange)
+    }
+
+    pub fn odd_even_transposition(node_data: &mut NodeContext) -> Result<i32, NodeError>{
+        Self::transposition(node_data, 0)
+    }
+
+    // the pairing of round r is the one odd_even_pairs_right gives for round r + phase
+    pub fn transposition(node_data: &mut NodeContext, phase: u32) -> Result<i32, NodeError>{
+
+        for round in 0..node_data.rounds {
+            node_data.begin_round(round)?;
+
+            let (link, compute_fn) =
+
+            match (odd_even_pairs_right(node_data.glb_pos, round + phase), node_data.rel_pos) {
+
+                // Current round -> odd round and node is at odd index or 
+                // Current round -> even round and node is at even index
+                (true, pos) if pos != RelativePos::Right => {
+                    (node_data.right_link.as_mut(),
+                    Some(should_swap_right as fn(PartialOrder, i32, i32) -> bool))
+                }
+
+                // Current round -> even round and node is at odd index or 
+                // Current round -> odd round and node is at even index
+                (false, pos) if pos != RelativePos::Left => {
+                    (node_data.left_link.as_mut(),
+                        Some(should_swap_left as fn(PartialOrder, i32, i32) -> bool))
+                }
+                _ => (None, None),
+            };
+
+            let mut changed = false;
+
+            if let (Some(link), Some(compute_fn)) =
+                 (link, compute_fn) {
+
+                let sent = node_data.byzantine.outgoing(node_data.num, link.side);
+                link.send(CommFlags::Exchange, &sent)?;
+                node_data.exchange_msgs += 1;
+
+                let rec_val = Self::receive_val(link)?;
+
+                // compute
+                if !node_data.byzantine.refuses_swap() &&
+                   compute_fn(node_data.partial_order, node_data.num, rec_val) {
+                    changed = true;
+                    node_data.num = rec_val;
+                }
+            }
+
+            if node_data.end_round(node_data.num, changed)? {
+                break;
+            }
+        }
+        Ok(node_data.num)
+    }
+}
+
+// handle_should know the data ?
+// starred 1
+// non - starred 0
+
+// a value along with its mark, marked values tell a node which area it sits in
+#[derive(Debug)]
+pub struct Marked {
+    num : i32,
+    is_marked : bool,
+}
+
+// Exchange : marked (1) + i32 (4)
+impl Wire for Marked {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.is_marked.encode(buffer);
+        self.num.encode(buffer);
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Marked> {
+        let (is_marked, num) = bytes.split_first()?;
+        Some(Marked { num: i32::decode(num)?, is_marked: bool::decode(&[*is_marked])? })
+    }
+}
+
+pub struct Sasaki;
+
+impl Sasaki {
+    fn receive_val(link:&mut Link) -> Result<Marked, NodeError>{
+        link.recv(CommFlags::Exchange)
+    }
+
+    fn send_recv_data (link:&mut Link, num:&Marked, byzantine:&mut Byzantine) -> Result<Marked, NodeError> {
+        let sent = Marked { num: byzantine.outgoing(num.num, link.side), is_marked: num.is_marked };
+        link.send(CommFlags::Exchange, &sent)?;
+        Self::receive_val(link)
+    }
+
+    pub fn sasaki(node_data: &mut NodeContext) -> Result<i32, NodeError> {
+        let mut area:i8 = match node_data.rel_pos{
+            RelativePos::Left => -1,
+            _ => 0,
+        };
+        let is_marked = node_data.rel_pos != RelativePos::Middle;
+        
+        let mut left_num = Marked{num:node_data.num, is_marked};
+        let mut right_num = Marked{num:node_data.num, is_marked};
+        let refuses_swap = node_data.byzantine.refuses_swap();
+
+
+        for round in 0..node_data.rounds {
+            node_data.begin_round(round)?;
+            let mut changed = false;
+
+            if let Some(link) = node_data.left_link.as_mut() {
+                let rec_val = Self::send_recv_data(link, &left_num, &mut node_data.byzantine)?;
+                node_data.exchange_msgs += 1;
+                log!("{} {} Received from left : {:?}", round, node_data.glb_pos, rec_val);
+                if !refuses_swap && should_swap_left(node_data.partial_order, left_num.num, rec_val.num) {
+                    // left_num = rec_val;
+                    if left_num.is_marked {
+                        area += 1;
+                    }
+
+                    if rec_val.is_marked {
+                        area -= 1;
+                    }
+
+                    changed = true;
+                    left_num = rec_val;
+                }
+            }
+
+            if let Some(link) = node_data.right_link.as_mut() {
+                let rec_val = Self::send_recv_data(link, &right_num, &mut node_data.byzantine)?;
+                node_data.exchange_msgs += 1;
+                log!("{} {} Received from right : {:?}", round, node_data.glb_pos, rec_val);
+                if !refuses_swap && should_swap_right(node_data.partial_order, right_num.num, rec_val.num) {
+                    changed = true;
+                    right_num = rec_val;
+                }
+            }
+
+            if node_data.rel_pos == RelativePos::Middle && !refuses_swap &&
+               (((left_num.num < right_num.num) && (node_data.partial_order == PartialOrder::GreaterThan)) || 
+                ((left_num.num > right_num.num) && (node_data.partial_order == PartialOrder::LessThan))) {
+                    changed = true;
+                    swap(&mut left_num, &mut right_num);
+            }
+
+            if node_data.end_round(Self::result(area, &left_num, &right_num), changed)? {
+                break;
+            }
+        }
+        Ok(Self::result(area, &left_num, &right_num))
+    }
+
+    // value the node would settle with if sorting stopped now
+    fn result(area:i8, left_num:&Marked, right_num:&Marked) -> i32 {
+        if area == -1 {
+            right_num.num 
+        }   
+        else {
+            left_num.num
+        }
+    }
+
+}
+
+pub struct Triplet;
+impl Triplet{
+    fn receive_val(link:&mut Link) -> Result<i32, NodeError> {
+        OddEven::receive_val(link)
+    }
+
+    fn send_num(link:&mut Link, num : i32) -> Result<(), NodeError> {
+        link.send(CommFlags::Exchange, &num)
+    }
+
+    pub fn triplet(node_data: &mut NodeContext) -> Result<i32, NodeError> {
+        for round in 0..node_data.rounds {
+            node_data.begin_round(round)?;
+            let pos = triplet_role(node_data.glb_pos, round, node_data.no_nodes);
+            let prev_num = node_data.num;
+            if pos == 1 {
+                let mut left_val = None;
+                let mut right_val = None;
+
+                // recieve values
+                if let Some(link) = node_data.left_link.as_mut() {
+                    left_val = Some(Self::receive_val(link)?);
+                } 
+
+                if let Some(link) = node_data.right_link.as_mut() {
+                    right_val = Some(Self::receive_val(link)?);
+                }
+
+                let mut nums: Vec<i32> = if node_data.byzantine.refuses_swap() {
+                    // hand every neighbour its own value back
+                    [left_val, Some(node_data.num), right_val].into_iter().flatten().collect()
+                }
+                else {
+                    let mut nums: Vec<i32> = [Some(node_data.num), left_val, right_val].into_iter().flatten().collect();
+                    // sort values in the partial order, the left neighbour takes the first
+                    match node_data.partial_order {
+                        PartialOrder::LessThan    => nums.sort(),
+                        PartialOrder::GreaterThan => nums.sort_by(|a, b| b.cmp(a)),
+                    }
+                    nums
+                };
+
+                // send appropriate values
+                if let Some(link) = node_data.left_link.as_mut() {
+                    let sent = node_data.byzantine.outgoing(nums.remove(0), link.side);
+                    Self::send_num(link, sent)?;
+                    node_data.exchange_msgs += 1;
+                    node_data.num = nums.remove(0);
+                }
+
+                if let Some(link) = node_data.right_link.as_mut() {
+                    if nums.len() == 2 {
+                        node_data.num = nums.remove(0);
+                    }
+                    let sent = node_data.byzantine.outgoing(nums.remove(0), link.side);
+                    Self::send_num(link, sent)?;
+                    node_data.exchange_msgs += 1;
+                }
+            }
+            else {
+                let link = if pos == 0 {
+                    node_data.right_link.as_mut()
+                }
+                else { 
+                    node_data.left_link.as_mut()
+                };
+                if let Some(link) = link {
+                    // send num
+                    let sent = node_data.byzantine.outgoing(node_data.num, link.side);
+                    Self::send_num(link, sent)?;
+                    node_data.exchange_msgs += 1;
+                    
+                    // updte num to the received num
+                    let rec_val = Self::receive_val(link)?;
+                    if !node_data.byzantine.refuses_swap() {
+                        node_data.num = rec_val;
+                    }
+                }
+            }
+
+
+            if node_data.end_round(node_data.num, node_data.num != prev_num)? {
+                break;
+            }
+        }
+        Ok(node_data.num)
+    }
+}
+
+impl SortAlgorithm for OddEven {
+    fn name(&self) -> &'static str {
+        Algo::OddEvenTransposition.name()
+    }
+
+    fn description(&self) -> &'static str {
+        "Odd Even Transposition, n rounds"
+    }
+
+    fn rounds(&self, no_nodes: u32) -> u32 {
+        get_rounds(Algo::OddEvenTransposition, no_nodes)
+    }
+
+    fn quiet_window(&self) -> u32 {
+        quiet_window(Algo::OddEvenTransposition)
+    }
+
+    fn comparators(&self, no_nodes: u32, round: u32) -> Vec<Comparator> {
+        comparators(Algo::OddEvenTransposition, no_nodes, round)
+    }
+
+    fn run(&self, ctx: &mut NodeContext) -> Result<Value, NodeError> {
+        Self::odd_even_transposition(ctx)
+    }
+}
+
+impl SortAlgorithm for Sasaki {
+    fn name(&self) -> &'static str {
+        Algo::Sasaki.name()
+    }
+
+    fn description(&self) -> &'static str {
+        "Sasaki, n - 1 rounds"
+    }
+
+    fn rounds(&self, no_nodes: u32) -> u32 {
+        get_rounds(Algo::Sasaki, no_nodes)
+    }
+
+    fn quiet_window(&self) -> u32 {
+        quiet_window(Algo::Sasaki)
+    }
+
+    fn comparators(&self, no_nodes: u32, round: u32) -> Vec<Comparator> {
+        comparators(Algo::Sasaki, no_nodes, round)
+    }
+
+    fn run(&self, ctx: &mut NodeContext) -> Result<Value, NodeError> {
+        Self::sasaki(ctx)
+    }
+}
+
+impl SortAlgorithm for Triplet {
+    fn name(&self) -> &'static str {
+        Algo::Triplet.name()
+    }
+
+    fn description(&self) -> &'static str {
+        "Triplet (Alternate n-1), n - 1 rounds"
+    }
+
+    fn rounds(&self, no_nodes: u32) -> u32 {
+        get_rounds(Algo::Triplet, no_nodes)
+    }
+
+    fn quiet_window(&self) -> u32 {
+        quiet_window(Algo::Triplet)
+    }
+
+    fn comparators(&self, no_nodes: u32, round: u32) -> Vec<Comparator> {
+        comparators(Algo::Triplet, no_nodes, round)
+    }
+
+    fn run(&self, ctx: &mut NodeContext) -> Result<Value, NodeError> {
+        Self::triplet(ctx)
+    }
+}
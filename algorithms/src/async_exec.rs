@@ -0,0 +1,230 @@
+use std::io::{ErrorKind, Write};
+use std::thread::sleep;
+use std::time::Instant;
+
+use utility::tls::NetStream;
+use utility::{get_rounds, log, Algo, CommFlags, MsgReader, PartialOrder, COUNTER_MSG, IDLE_WAIT};
+
+use crate::node_utils::{Link, NodeContext, NodeError, RelativePos};
+use crate::termination::Termination;
+use crate::{SortAlgorithm, Value};
+
+// Asynchronous execution model : no rounds and no lockstep, every node runs an
+// event loop over non-blocking streams and acts as soon as a message arrives.
+//
+// Async Odd-Even : the left node of every pair drives the compare-exchange.
+// It proposes its value, the right node keeps the one belonging to it and replies
+// with the other. While waiting for a reply a node defers proposals from its left,
+// the chain of waiting nodes always ends at the right end so it cannot deadlock.
+// A node whose value changed through its right pair pokes its left neighbour to
+// propose again. Once nothing is left to do anywhere the line is sorted, which the
+// distributor detects from the message counters the nodes report.
+
+// what a node keeps track of between events
+struct AsyncState {
+    awaiting_reply : Option<Instant>,   // proposed to the right at, reply not received yet
+    right_dirty    : bool,          // right pair has to be compared (again)
+    deferred       : Option<i32>,   // proposal from the left received while awaiting reply
+    sent           : u32,
+    received       : u32,
+}
+
+impl AsyncState {
+
+    // neighbour messages : flag + i32
+    fn send(link: &mut Link, flag: CommFlags, num: i32, sent: &mut u32) -> Result<(), NodeError> {
+        link.send(flag, &num)?;
+        *sent += 1;
+        Ok(())
+    }
+
+    fn report(stream: &mut NetStream, flag: CommFlags, idle: bool, sent: u32, received: u32) {
+        let mut buffer = [0u8; COUNTER_MSG];
+        buffer[0] = flag as u8;
+        buffer[1] = idle as u8;
+        buffer[2..6].copy_from_slice(&sent.to_le_bytes());
+        buffer[6..10].copy_from_slice(&received.to_le_bytes());
+        stream.write_all(&buffer).expect("Failed to report to distributor");
+    }
+
+    // A neighbour only closes its streams after the distributor stopped the run,
+    // its Stop may reach this node a bit later so stop polling the link until then.
+    // Anything else going wrong on the link (a corrupted message ...) ends the run
+    fn poll(link: &mut Link, closed: &mut bool) -> Result<Option<(u8, i32)>, NodeError> {
+        match link.try_recv() {
+            Ok(msg) => Ok(msg),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                *closed = true;
+                Ok(None)
+            },
+            Err(e) => {
+                log!("Failed to read from {:?} neighbour : {}", link.side, e);
+                Err(NodeError::from_io(e, link.side))
+            },
+        }
+    }
+
+    // right node of a pair : keeps its share of the two values and returns the other
+    fn handle_proposal(&mut self, node_data: &mut NodeContext, rec_val: i32) -> Result<(), NodeError> {
+        let keep_received = match node_data.partial_order {
+            PartialOrder::LessThan    => rec_val > node_data.num,
+            PartialOrder::GreaterThan => rec_val < node_data.num,
+        };
+
+        let reply = if keep_received {
+            let reply = node_data.num;
+            node_data.num = rec_val;
+            self.right_dirty = node_data.right_link.is_some();
+            reply
+        } else {
+            rec_val
+        };
+
+        let link = node_data.left_link.as_mut().expect("Proposal without a left neighbour");
+        Self::send(link, CommFlags::Reply, reply, &mut self.sent)
+    }
+
+    // left node of a pair : takes the value handed back by the right node
+    fn handle_reply(&mut self, node_data: &mut NodeContext, rec_val: i32) -> Result<(), NodeError> {
+        self.awaiting_reply = None;
+        if rec_val != node_data.num {
+            node_data.num = rec_val;
+            if let Some(link) = node_data.left_link.as_mut() {
+                Self::send(link, CommFlags::Poke, 0, &mut self.sent)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn async_odd_even(node_data: &mut NodeContext) -> Result<i32, NodeError> {
+        let mut distributor = match &node_data.termination {
+            Termination::Quiescence(stream) => stream.try_clone().expect("Failed to clone distributor stream"),
+            _ => panic!("Async execution needs the distributor to detect quiescence"),
+        };
+
+        let mut state = AsyncState {
+            awaiting_reply : None,
+            right_dirty    : node_data.right_link.is_some(),
+            deferred       : None,
+            sent           : 0,
+            received       : 0,
+        };
+
+        // the distributor stream is shared with the one Finish or Abort is sent on,
+        // the links go back to blocking on their own once the next run of a pool receives
+        distributor.set_nonblocking(true).expect("Failed to set non-blocking");
+        let outcome = state.event_loop(node_data, &mut distributor);
+        distributor.set_nonblocking(false).expect("Failed to set blocking");
+
+        node_data.exchange_msgs = state.sent;
+        outcome.map(|_| node_data.num)
+    }
+
+    // handles the events until the distributor stops the run, or a neighbour fails
+    fn event_loop(&mut self, node_data: &mut NodeContext, distributor: &mut NetStream) -> Result<(), NodeError> {
+        let mut dist_reader  = MsgReader::new(1);
+        let mut last_report  = None;
+        let (mut left_closed, mut right_closed) = (false, false);
+
+        loop {
+            let mut progressed = false;
+
+            if let Some(link) = node_data.left_link.as_mut().filter(|_| !left_closed) {
+                if let Some((flag, rec_val)) = Self::poll(link, &mut left_closed)? {
+                    progressed = true;
+                    self.received += 1;
+                    if flag != CommFlags::Propose as u8 {
+                        log!("Unexpected message from left : {}", flag);
+                        return Err(NodeError::Protocol(RelativePos::Left));
+                    }
+                    self.deferred = Some(rec_val);
+                }
+            }
+
+            if let Some(link) = node_data.right_link.as_mut().filter(|_| !right_closed) {
+                if let Some((flag, rec_val)) = Self::poll(link, &mut right_closed)? {
+                    progressed = true;
+                    self.received += 1;
+                    match flag {
+                        flag if flag == CommFlags::Reply as u8 => self.handle_reply(node_data, rec_val)?,
+                        flag if flag == CommFlags::Poke as u8  => self.right_dirty = true,
+                        def_val => {
+                            log!("Unexpected message from right : {}", def_val);
+                            return Err(NodeError::Protocol(RelativePos::Right));
+                        },
+                    }
+                }
+            }
+
+            if let Some(msg) = dist_reader.poll(distributor).expect("Distributor disconnected") {
+                progressed = true;
+                match msg[0] {
+                    flag if flag == CommFlags::Probe as u8 => {
+                        let idle = self.awaiting_reply.is_none() && !self.right_dirty && self.deferred.is_none();
+                        Self::report(distributor, CommFlags::Status, idle, self.sent, self.received);
+                        node_data.control_msgs += 1;
+                    },
+                    flag if flag == CommFlags::Stop as u8 => return Ok(()),
+                    def_val => panic!("Unexpected message from distributor : {}", def_val),
+                }
+            }
+
+            if self.awaiting_reply.is_none() {
+                if let Some(rec_val) = self.deferred.take() {
+                    self.handle_proposal(node_data, rec_val)?;
+                }
+            }
+
+            if self.right_dirty && self.awaiting_reply.is_none() {
+                let link = node_data.right_link.as_mut().expect("No right neighbour to propose to");
+                Self::send(link, CommFlags::Propose, node_data.num, &mut self.sent)?;
+                self.awaiting_reply = Some(Instant::now());
+                self.right_dirty = false;
+            }
+
+            // ran out of work, let the distributor know once per change of counters
+            let idle = self.awaiting_reply.is_none() && !self.right_dirty && self.deferred.is_none();
+            if idle && last_report != Some((self.sent, self.received)) {
+                Self::report(distributor, CommFlags::Idle, true, self.sent, self.received);
+                node_data.control_msgs += 1;
+                last_report = Some((self.sent, self.received));
+            }
+
+            // a proposal or its reply may have been dropped by injected faults
+            if let (Some(proposed_at), Some(timeout)) = (self.awaiting_reply, node_data.timeout) {
+                if proposed_at.elapsed() > timeout {
+                    return Err(NodeError::Timeout(RelativePos::Right));
+                }
+            }
+
+            if !progressed {
+                sleep(IDLE_WAIT);
+            }
+        }
+    }
+}
+
+pub struct AsyncOddEven;
+
+impl SortAlgorithm for AsyncOddEven {
+    fn name(&self) -> &'static str {
+        Algo::AsyncOddEven.name()
+    }
+
+    fn description(&self) -> &'static str {
+        "Async Odd Even, no rounds, runs until no node has work left"
+    }
+
+    fn rounds(&self, no_nodes: u32) -> u32 {
+        get_rounds(Algo::AsyncOddEven, no_nodes)
+    }
+
+    fn is_async(&self) -> bool {
+        true
+    }
+
+    // needs Termination::Quiescence, the distributor decides when the line is done
+    fn run(&self, ctx: &mut NodeContext) -> Result<Value, NodeError> {
+        AsyncState::async_odd_even(ctx)
+    }
+}
@@ -0,0 +1,150 @@
+use std::io;
+use std::thread::sleep;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use utility::faults::{ByzantineMode, Delay, EdgeFaults};
+use utility::log;
+
+use crate::node_utils::RelativePos;
+use crate::transport::Channel;
+
+// made up values stay in a printable range
+const LIE_RANGE : i32 = i16::MAX as i32;
+
+// Channel injecting the faults configured for an edge into the messages sent over it,
+// each one may be delayed, dropped, corrupted or held back and sent after the next one.
+// Receiving is left to the wrapped channel.
+#[derive(Debug)]
+pub struct FaultyChannel {
+    inner  : Box<dyn Channel>,
+    faults : EdgeFaults,
+    rng    : StdRng,
+    held   : Option<Vec<u8>>,   // message held back to be sent after the next one
+}
+
+fn sample_delay(delay: Delay, rng: &mut StdRng) -> Duration {
+    let ms = match delay {
+        Delay::Fixed(ms)          => ms as f64,
+        Delay::Uniform(low, high) => rng.random_range(low..=high) as f64,
+        Delay::Exponential(mean)  => -(mean as f64) * (1.0 - rng.random::<f64>()).ln(),
+    };
+    Duration::from_secs_f64(ms / 1000.0)
+}
+
+impl FaultyChannel {
+    // every link gets its own generator derived from the run's seed, so a
+    // run with the same seed injects the same faults on the same messages.
+    // A channel without faults on its edge is returned as is
+    pub fn wrap(inner: Box<dyn Channel>, faults: EdgeFaults, seed: u64) -> Box<dyn Channel> {
+        if faults.is_empty() {
+            return inner;
+        }
+        Box::new(FaultyChannel { inner, faults, rng: StdRng::seed_from_u64(seed), held: None })
+    }
+}
+
+// Behaviour of the node in the exchanges, honest unless the distributor marked it Byzantine
+#[derive(Debug)]
+pub struct Byzantine {
+    mode : Option<ByzantineMode>,
+    rng  : StdRng,
+    lie  : i32,   // value told to both neighbours this round (Lie)
+}
+
+impl Byzantine {
+    pub fn honest() -> Byzantine {
+        Byzantine { mode: None, rng: StdRng::seed_from_u64(0), lie: 0 }
+    }
+
+    pub fn new(mode: ByzantineMode, seed: u64) -> Byzantine {
+        Byzantine { mode: Some(mode), rng: StdRng::seed_from_u64(seed), lie: 0 }
+    }
+
+    pub fn new_round(&mut self) {
+        if self.mode == Some(ByzantineMode::Lie) {
+            self.lie = self.rng.random_range(-LIE_RANGE..=LIE_RANGE);
+        }
+    }
+
+    // value sent to the neighbour on `side` in place of `num`
+    pub fn outgoing(&mut self, num: i32, side: RelativePos) -> i32 {
+        let sent = match self.mode {
+            Some(ByzantineMode::Lie) => self.lie,
+            Some(ByzantineMode::Inconsistent) => self.rng.random_range(-LIE_RANGE..=LIE_RANGE),
+            _ => return num,
+        };
+        log!("Telling {:?} neighbour {} instead of {}", side, sent, num);
+        sent
+    }
+
+    // a node refusing to swap keeps its value whatever its neighbours send
+    pub fn refuses_swap(&self) -> bool {
+        self.mode == Some(ByzantineMode::Refuse)
+    }
+}
+
+impl Channel for FaultyChannel {
+    fn send(&mut self, msg: &[u8]) -> io::Result<()> {
+        if let Some(drop) = self.faults.drop {
+            if self.rng.random_bool(drop) {
+                log!("Dropped {:?}", msg);
+                return Ok(());
+            }
+        }
+
+        if let Some(delay) = self.faults.delay {
+            sleep(sample_delay(delay, &mut self.rng));
+        }
+
+        let mut msg = msg.to_vec();
+        if let Some(corrupt) = self.faults.corrupt {
+            if !msg.is_empty() && self.rng.random_bool(corrupt) {
+                let bit = self.rng.random_range(0..msg.len() * 8);
+                msg[bit / 8] ^= 1 << (bit % 8);
+                log!("Corrupted bit {} of {:?}", bit, msg);
+            }
+        }
+
+        if let Some(reorder) = self.faults.reorder {
+            if self.held.is_none() && self.rng.random_bool(reorder) {
+                log!("Holding back {:?}", msg);
+                self.held = Some(msg);
+                return Ok(());
+            }
+        }
+
+        self.inner.send(&msg)?;
+        if let Some(held) = self.held.take() {
+            self.inner.send(&held)?;
+        }
+        Ok(())
+    }
+
+    fn recv(&mut self) -> io::Result<Vec<u8>> {
+        self.inner.recv()
+    }
+
+    fn try_recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.inner.try_recv()
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner.set_timeout(timeout)
+    }
+
+    // a message still held back has no next one to follow and is lost,
+    // unless a reliable link resends it
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    fn idle(&mut self) -> io::Result<()> {
+        self.inner.idle()
+    }
+
+    fn resent(&self) -> u32 {
+        self.inner.resent()
+    }
+}
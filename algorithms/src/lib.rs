@@ -0,0 +1,71 @@
+// Sorting algorithms run by every node of the line, along with what they run on :
+// the links to the neighbours (over any Channel), termination detection and the injected faults.
+// Algorithms kept in other crates implement SortAlgorithm against NodeContext,
+// register themselves with register_algo! and are run by the node like the ones here.
+
+pub mod algos;
+pub mod async_exec;
+pub mod faults;
+pub mod node_utils;
+pub mod registry;
+pub mod reliable;
+pub mod termination;
+pub mod transport;
+
+pub use algos::{OddEven, Sasaki, Triplet};
+pub use async_exec::AsyncOddEven;
+pub use node_utils::{Link, NodeContext, NodeError, RelativePos};
+pub use registry::AlgoEntry;
+pub use reliable::ReliableChannel;
+pub use transport::{Channel, ChecksumChannel, MemChannel, NetChannel, TcpChannel, Wire};
+pub use utility::network::Comparator;
+// used by register_algo!
+pub use inventory;
+
+// value sorted by the nodes
+pub type Value = i32;
+
+pub trait SortAlgorithm {
+    // name given to --algo
+    fn name(&self) -> &'static str;
+
+    // one line shown by list-algos
+    fn description(&self) -> &'static str {
+        ""
+    }
+
+    // network shapes the algorithm sorts on
+    fn topologies(&self) -> &'static [&'static str] {
+        &["line"]
+    }
+
+    // fewest nodes the algorithm runs on, a single node holds its value as is
+    fn min_nodes(&self) -> u32 {
+        1
+    }
+
+    // rounds needed on a line of `no_nodes` nodes, 0 for algorithms without rounds
+    fn rounds(&self, no_nodes: u32) -> u32;
+
+    // runs without rounds, the distributor tells the nodes when the whole line ran
+    // out of work (Termination::Quiescence)
+    fn is_async(&self) -> bool {
+        false
+    }
+
+    // no.of consecutive rounds without any change after which the line can stop early
+    fn quiet_window(&self) -> u32 {
+        1
+    }
+
+    // groups of positions compared in a round, drawn by the diagrams
+    // empty for algorithms without a fixed network
+    fn comparators(&self, _no_nodes: u32, _round: u32) -> Vec<Comparator> {
+        Vec::new()
+    }
+
+    // runs on one node until it is done, returns the value the node ends up with.
+    // Neighbours are reached through the links of the context and every round
+    // is wrapped in ctx.begin_round / ctx.end_round
+    fn run(&self, ctx: &mut NodeContext) -> Result<Value, NodeError>;
+}
@@ -0,0 +1,212 @@
+use std::io::{self, ErrorKind};
+use std::panic;
+use std::thread;
+use std::time::Duration;
+use num_derive::FromPrimitive;
+use utility::{log, CommFlags, PartialOrder};
+
+use crate::faults::Byzantine;
+use crate::termination::Termination;
+use crate::transport::{Channel, Wire};
+
+#[derive(FromPrimitive, PartialEq, Debug, Clone, Copy)]
+pub enum RelativePos {
+    Left,
+    Right,
+    Middle,
+    Alone,    // the only node of the line, has no neighbours
+}
+
+#[derive(Debug)]
+pub struct Link {
+    pub channel : Box<dyn Channel>,
+    pub side    : RelativePos,   // which neighbour the link leads to (Left / Right)
+}
+
+impl Link {
+    pub fn new(channel: Box<dyn Channel>, side: RelativePos) -> Link {
+        Link { channel, side }
+    }
+
+    // sends `value` to the neighbour in a message marked with `flag`
+    pub fn send<T: Wire>(&mut self, flag: CommFlags, value: &T) -> Result<(), NodeError> {
+        let mut msg = vec![flag as u8];
+        value.encode(&mut msg);
+        self.channel.send(&msg).map_err(|_| NodeError::NeighbourLost(self.side))
+    }
+
+    // waits for the neighbour's next message, which has to be marked with `flag`
+    // any other message is a protocol error
+    pub fn recv<T: Wire>(&mut self, flag: CommFlags) -> Result<T, NodeError> {
+        let msg = self.channel.recv().map_err(|e| {
+            log!("Failed to read data :{}", e);
+            NodeError::from_io(e, self.side)
+        })?;
+        log!("Received from {:?} neighbour : {:?}", self.side, msg);
+
+        let flag = flag as u8;
+        match Self::decode(&msg) {
+            Some((received, value)) if received == flag => Ok(value),
+            _ => {
+                log!("Expected a message {} from {:?} neighbour, got {:?}", flag, self.side, msg);
+                Err(NodeError::Protocol(self.side))
+            },
+        }
+    }
+
+    // the neighbour's next message with its flag if it has arrived, without waiting
+    // one that does not hold a T is refused with ErrorKind::InvalidData
+    pub fn try_recv<T: Wire>(&mut self) -> io::Result<Option<(u8, T)>> {
+        let Some(msg) = self.channel.try_recv()? else {
+            return Ok(None);
+        };
+        let decoded = Self::decode(&msg).ok_or_else(|| io::Error::new(ErrorKind::InvalidData,
+            format!("Malformed message from {:?} neighbour : {:?}", self.side, msg)))?;
+        Ok(Some(decoded))
+    }
+
+    // called once the algorithm is done with the link
+    pub fn flush(&mut self) -> Result<(), NodeError> {
+        self.channel.flush().map_err(|e| {
+            log!("Failed to flush the link : {}", e);
+            NodeError::from_io(e, self.side)
+        })
+    }
+
+    // called while the node waits on the distributor
+    pub fn idle(&mut self) -> Result<(), NodeError> {
+        self.channel.idle().map_err(|e| NodeError::from_io(e, self.side))
+    }
+
+    fn decode<T: Wire>(msg: &[u8]) -> Option<(u8, T)> {
+        let (flag, value) = msg.split_first()?;
+        Some((*flag, T::decode(value)?))
+    }
+}
+
+// Why a node could not finish sorting
+#[derive(Debug)]
+pub enum NodeError {
+    // the link to the neighbour on this side closed or broke in the middle of the rounds
+    NeighbourLost(RelativePos),
+    // the neighbour on this side did not send anything in time (faults injected)
+    Timeout(RelativePos),
+    // a message from the neighbour on this side failed its checksum, came from another run
+    // or was not the one expected
+    Protocol(RelativePos),
+    // ordered to crash before the round (crash-stop fault), the node binary exits without a word
+    Crashed,
+    // the neighbour on this side could not be connected to or did not connect in time,
+    // the rounds never started
+    NeverConnected(RelativePos),
+    // the stream to the distributor broke, or the distributor sent something else than
+    // expected, while the node waited on it (early termination vote)
+    DistributorLost,
+}
+
+impl NodeError {
+    // classifies a failed read / write on the link to the neighbour on `side`
+    pub fn from_io(e: io::Error, side: RelativePos) -> NodeError {
+        match e.kind() {
+            ErrorKind::WouldBlock | ErrorKind::TimedOut => NodeError::Timeout(side),
+            ErrorKind::InvalidData => NodeError::Protocol(side),
+            _ => NodeError::NeighbourLost(side),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NodeContext {
+    pub partial_order : PartialOrder,
+    pub left_link     : Option<Link>,
+    pub right_link    : Option<Link>, 
+    pub rounds        : u32,
+    pub no_nodes      : u32,
+    pub rel_pos  : RelativePos,    // position relative to other nodes
+    pub glb_pos    : u32,         // not used by sasaki
+    pub num           : i32,
+    pub trace         : Option<Vec<i32>>, // value held after each round, if requested
+    pub termination   : Termination,
+    pub rounds_used   : u32,
+    pub exchange_msgs : u32,   // messages sent to neighbours by the algorithm
+    pub control_msgs  : u32,   // messages sent only to detect termination
+    pub crash_round   : Option<u32>,   // crash-stop fault : exit right before this round
+    pub timeout       : Option<Duration>,   // how long to wait on a neighbour when faults are injected
+    pub byzantine     : Byzantine,
+}
+
+impl NodeContext {
+    // called by the algorithms at the end of every round with the value the node 
+    // currently settles with and whether anything it holds changed in the round
+    // returns true if sorting should stop early
+    pub fn end_round(&mut self, num: i32, changed: bool) -> Result<bool, NodeError> {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(num);
+        }
+        self.num = num;
+        self.rounds_used += 1;
+        self.termination.round_done(changed, self.rounds_used, self.rel_pos, self.left_link.as_mut(),
+                                    self.right_link.as_mut(), &mut self.control_msgs)
+    }
+
+    // called once the algorithm returned, both links are flushed at once as each
+    // may wait on the neighbour flushing its end, see Channel::flush
+    pub fn flush_links(&mut self) -> Result<(), NodeError> {
+        let links = [self.left_link.as_mut(), self.right_link.as_mut()];
+        thread::scope(|scope| {
+            let flushing: Vec<_> = links.into_iter().flatten()
+                .map(|link| scope.spawn(move || link.flush()))
+                .collect();
+            flushing.into_iter()
+                .try_for_each(|flushing| flushing.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+        })
+    }
+
+    // messages resent over both links because they were not acknowledged in time
+    pub fn resent_msgs(&self) -> u32 {
+        [self.left_link.as_ref(), self.right_link.as_ref()].into_iter().flatten()
+            .map(|link| link.channel.resent())
+            .sum()
+    }
+
+    // called by the algorithms at the start of every round (0-based)
+    // a node ordered to crash stops here with NodeError::Crashed, left for the caller to act out
+    pub fn begin_round(&mut self, round: u32) -> Result<(), NodeError> {
+        if self.crash_round == Some(round + 1) {
+            log!("Crashing before round {}", round + 1);
+            return Err(NodeError::Crashed);
+        }
+        self.byzantine.new_round();
+        Ok(())
+    }
+}
+
+impl NodeContext {
+    // a node running all the rounds with no faults, position taken from the links it has.
+    // The rest can be set on the context before running the algorithm
+    pub fn new(num: i32, glb_pos: u32, no_nodes: u32, rounds: u32, partial_order: PartialOrder,
+               left_link: Option<Link>, right_link: Option<Link>) -> NodeContext {
+        let rel_pos = match (&left_link, &right_link) {
+            (None, None)    => RelativePos::Alone,
+            (None, Some(_)) => RelativePos::Left,
+            (Some(_), None) => RelativePos::Right,
+            _               => RelativePos::Middle,
+        };
+        NodeContext { partial_order, left_link, right_link, rounds, no_nodes, rel_pos, glb_pos, num,
+                      trace: None, termination: Termination::Fixed, rounds_used: 0, exchange_msgs: 0,
+                      control_msgs: 0, crash_round: None, timeout: None, byzantine: Byzantine::honest() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_crash_fault_stops_the_node_with_an_error() {
+        let mut ctx = NodeContext::new(5, 1, 1, 3, PartialOrder::LessThan, None, None);
+        ctx.crash_round = Some(2);
+        assert!(ctx.begin_round(0).is_ok());
+        assert!(matches!(ctx.begin_round(1), Err(NodeError::Crashed)));
+    }
+}
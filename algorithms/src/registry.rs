@@ -0,0 +1,81 @@
+use std::sync::OnceLock;
+
+use crate::{AsyncOddEven, OddEven, Sasaki, SortAlgorithm, Triplet};
+
+// Algorithms known to the binaries, by the name given to --algo.
+// The distributor looks them up to validate and list them, the node to run the
+// one named in the Order. Besides the built in ones, any crate linked into a binary
+// adds its own with register_algo!, they are collected when the registry is first used.
+// Both binaries have to be linked with the same crates.
+
+#[derive(Clone, Copy, Debug)]
+pub struct AlgoEntry {
+    construct : fn() -> Box<dyn SortAlgorithm>,
+}
+
+impl AlgoEntry {
+    pub const fn new(construct: fn() -> Box<dyn SortAlgorithm>) -> AlgoEntry {
+        AlgoEntry { construct }
+    }
+
+    pub fn construct(&self) -> Box<dyn SortAlgorithm> {
+        (self.construct)()
+    }
+
+    // as the algorithm names itself, see SortAlgorithm::name
+    pub fn name(&self) -> &'static str {
+        self.construct().name()
+    }
+}
+
+inventory::collect!(AlgoEntry);
+
+// Registers an algorithm from any crate, e.g. `algorithms::register_algo!(EvenOdd);`
+// at the top level of the crate. A binary picks it up once the crate is linked in,
+// `use even_odd as _;` is enough when nothing else of the crate is used
+#[macro_export]
+macro_rules! register_algo {
+    ($algo:expr) => {
+        $crate::inventory::submit! {
+            $crate::AlgoEntry::new(|| Box::new($algo))
+        }
+    };
+}
+
+static BUILT_IN : [AlgoEntry; 4] = [
+    AlgoEntry::new(|| Box::new(OddEven)),
+    AlgoEntry::new(|| Box::new(Sasaki)),
+    AlgoEntry::new(|| Box::new(Triplet)),
+    AlgoEntry::new(|| Box::new(AsyncOddEven)),
+];
+
+static ENTRIES : OnceLock<Vec<&'static AlgoEntry>> = OnceLock::new();
+
+// every algorithm in the order list-algos shows them : the built in ones, then the
+// registered ones by name. A name taken twice is refused
+pub fn entries() -> &'static [&'static AlgoEntry] {
+    ENTRIES.get_or_init(|| {
+        let mut registered: Vec<&'static AlgoEntry> = inventory::iter::<AlgoEntry>.into_iter().collect();
+        registered.sort_by_key(|entry| entry.name());
+        let entries: Vec<&'static AlgoEntry> = BUILT_IN.iter().chain(registered).collect();
+
+        for (i, entry) in entries.iter().enumerate() {
+            if entries[..i].iter().any(|other| other.name() == entry.name()) {
+                panic!("Algorithm '{}' is registered twice", entry.name());
+            }
+        }
+        entries
+    })
+}
+
+pub fn names() -> Vec<&'static str> {
+    entries().iter().map(|entry| entry.name()).collect()
+}
+
+pub fn find(name: &str) -> Option<&'static AlgoEntry> {
+    entries().iter().copied().find(|entry| entry.name() == name)
+}
+
+pub fn lookup(name: &str) -> Option<Box<dyn SortAlgorithm>> {
+    find(name).map(|entry| entry.construct())
+}
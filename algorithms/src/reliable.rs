@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, ErrorKind};
+use std::time::{Duration, Instant};
+
+use utility::log;
+
+use crate::transport::Channel;
+
+// Reliable delivery over a channel that may drop, delay, reorder, duplicate or (under
+// ChecksumChannel) refuse messages. Every message is numbered per link and acknowledged
+// by the other end, one that is not acknowledged in time is sent again. Messages are
+// handed over in the order they were sent and exactly once, duplicates are discarded,
+// so the rounds of the algorithms stay as synchronous as over a perfect link.
+//
+// Retransmission happens while the node waits on the link (recv, try_recv), while it
+// waits on something else (idle) and in flush, which the node calls once it is done with
+// the link so its last messages get through.
+//
+// On the wire : [DATA][seq u32][message] | [ACK][seq u32], an ack carries the seq of
+// the next message expected, acknowledging every one before it
+
+const DATA : u8 = 0;
+const ACK  : u8 = 1;
+
+// kind + seq
+const HEADER : usize = 5;
+
+// how many retransmission periods flush waits for a peer that is quiet before returning,
+// the peer resends its last message if the ack of it was lost
+const LINGER_PERIODS : u32 = 3;
+
+#[derive(Debug)]
+pub struct ReliableChannel {
+    inner      : Box<dyn Channel>,
+    retransmit : Duration,                  // how long a message waits for its ack before it is sent again
+    give_up    : Option<Duration>,          // how long recv waits for the next message, None for ever
+    next_seq   : u32,                       // of the next message sent
+    unacked    : VecDeque<(u32, Vec<u8>)>,  // sent but not acknowledged yet, oldest first
+    last_sent  : Instant,                   // when the oldest of unacked was last sent
+    expected   : u32,                       // seq of the next message to hand over
+    early      : BTreeMap<u32, Vec<u8>>,    // arrived ahead of `expected`
+    ready      : VecDeque<Vec<u8>>,         // in order, not handed over yet
+    resent     : u32,
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+fn is_gone(e: &io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::UnexpectedEof | ErrorKind::BrokenPipe | ErrorKind::ConnectionReset)
+}
+
+impl ReliableChannel {
+    // `retransmit` should be above the round trip of the link, a shorter one only
+    // resends messages that get discarded as duplicates
+    pub fn wrap(mut inner: Box<dyn Channel>, retransmit: Duration) -> io::Result<Box<dyn Channel>> {
+        inner.set_timeout(Some(retransmit))?;
+        Ok(Box::new(ReliableChannel {
+            inner, retransmit, give_up: None, next_seq: 0, unacked: VecDeque::new(), last_sent: Instant::now(),
+            expected: 0, early: BTreeMap::new(), ready: VecDeque::new(), resent: 0,
+        }))
+    }
+
+    fn send_data(&mut self, seq: u32, msg: &[u8]) -> io::Result<()> {
+        let mut data = Vec::with_capacity(HEADER + msg.len());
+        data.push(DATA);
+        data.extend_from_slice(&seq.to_le_bytes());
+        data.extend_from_slice(msg);
+        self.inner.send(&data)
+    }
+
+    fn send_ack(&mut self) -> io::Result<()> {
+        let mut ack = vec![ACK];
+        ack.extend_from_slice(&self.expected.to_le_bytes());
+        self.inner.send(&ack)
+    }
+
+    // sends the unacknowledged messages again once they waited `retransmit` for their ack
+    fn resend_due(&mut self) -> io::Result<()> {
+        if self.unacked.is_empty() || self.last_sent.elapsed() < self.retransmit {
+            return Ok(());
+        }
+        let unacked: Vec<(u32, Vec<u8>)> = self.unacked.iter().cloned().collect();
+        for (seq, msg) in unacked {
+            log!("Resending {} : {:?}", seq, msg);
+            self.send_data(seq, &msg)?;
+            self.resent += 1;
+        }
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+
+    // handles one message of the inner channel
+    fn take(&mut self, msg: Vec<u8>) -> io::Result<()> {
+        if msg.len() < HEADER {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Message too short for its sequence number"));
+        }
+        let seq = u32::from_le_bytes(msg[1..HEADER].try_into().unwrap());
+        match msg[0] {
+            ACK => {
+                let acked = self.unacked.iter().take_while(|(sent, _)| *sent < seq).count();
+                if acked > 0 {
+                    self.unacked.drain(..acked);
+                    self.last_sent = Instant::now();
+                }
+                Ok(())
+            },
+            DATA => {
+                if seq < self.expected || self.early.contains_key(&seq) {
+                    log!("Discarded duplicate {} : {:?}", seq, &msg[HEADER..]);
+                }
+                else {
+                    self.early.insert(seq, msg[HEADER..].to_vec());
+                    while let Some(msg) = self.early.remove(&self.expected) {
+                        self.ready.push_back(msg);
+                        self.expected += 1;
+                    }
+                }
+                // acknowledged again for a duplicate, the first ack may have been lost
+                self.send_ack()
+            },
+            kind => Err(io::Error::new(ErrorKind::InvalidData, format!("Unknown message kind {}", kind))),
+        }
+    }
+
+    // handles what has already arrived, acks included, so nothing acknowledged is resent
+    fn drain(&mut self) -> io::Result<()> {
+        loop {
+            match self.inner.try_recv() {
+                Ok(Some(msg)) => self.take(msg)?,
+                Ok(None) => return Ok(()),
+                Err(e) if Self::refused(&e) => (),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // a message refused by the inner channel is treated as lost, the peer sends it again
+    fn refused(e: &io::Error) -> bool {
+        if e.kind() == ErrorKind::InvalidData {
+            log!("Discarded a refused message : {}", e);
+            return true;
+        }
+        false
+    }
+}
+
+impl Channel for ReliableChannel {
+    fn send(&mut self, msg: &[u8]) -> io::Result<()> {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        if self.unacked.is_empty() {
+            self.last_sent = Instant::now();
+        }
+        self.unacked.push_back((seq, msg.to_vec()));
+        self.send_data(seq, msg)
+    }
+
+    fn recv(&mut self) -> io::Result<Vec<u8>> {
+        let deadline = self.give_up.map(|give_up| Instant::now() + give_up);
+        loop {
+            if let Some(msg) = self.ready.pop_front() {
+                return Ok(msg);
+            }
+            self.drain()?;
+            if let Some(msg) = self.ready.pop_front() {
+                return Ok(msg);
+            }
+            self.resend_due()?;
+            match self.inner.recv() {
+                Ok(msg) => self.take(msg)?,
+                Err(e) if is_timeout(&e) || Self::refused(&e) => (),
+                Err(e) => return Err(e),
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(io::Error::new(ErrorKind::TimedOut, "No message in time"));
+            }
+        }
+    }
+
+    fn try_recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.ready.is_empty() {
+            self.drain()?;
+            self.resend_due()?;
+        }
+        Ok(self.ready.pop_front())
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.give_up = timeout;
+        Ok(())
+    }
+
+    // acknowledges and resends, the messages that arrive wait for the next recv
+    fn idle(&mut self) -> io::Result<()> {
+        self.drain()?;
+        self.resend_due()
+    }
+
+    // waits for every message sent to be acknowledged, then for the peer to be quiet for
+    // a while. A peer that is gone is done with the link and got everything it waited for
+    fn flush(&mut self) -> io::Result<()> {
+        let linger = self.retransmit * LINGER_PERIODS;
+        let mut heard = Instant::now();
+        let mut waiting = Instant::now();
+        while !self.unacked.is_empty() || heard.elapsed() < linger {
+            let result = self.resend_due().and_then(|()| self.inner.recv());
+            match result.and_then(|msg| self.take(msg)) {
+                Ok(()) => heard = Instant::now(),
+                Err(e) if is_gone(&e) => return Ok(()),
+                Err(e) if is_timeout(&e) || Self::refused(&e) => (),
+                Err(e) => return Err(e),
+            }
+            if !self.unacked.is_empty() && self.give_up.is_some_and(|give_up| waiting.elapsed() >= give_up) {
+                return Err(io::Error::new(ErrorKind::TimedOut, "Messages not acknowledged in time"));
+            }
+            if self.unacked.is_empty() {
+                waiting = Instant::now();
+            }
+        }
+        Ok(())
+    }
+
+    fn resent(&self) -> u32 {
+        self.resent
+    }
+}
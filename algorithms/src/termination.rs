@@ -0,0 +1,123 @@
+use std::io::{self, Write};
+use std::time::Duration;
+
+use utility::tls::NetStream;
+use utility::{log, CommFlags, MsgReader};
+
+use crate::node_utils::{Link, NodeError, RelativePos};
+
+// how often the links are kept going while waiting for the verdict of a vote
+const VERDICT_WAIT : Duration = Duration::from_millis(5);
+
+// How a node decides to stop before the fixed round bound
+#[derive(Debug)]
+pub enum Termination {
+    // always runs all the rounds given by get_rounds
+    Fixed,
+
+    // votes to the distributor after every round whether its value changed,
+    // the distributor replies whether the whole line has been quiet long enough
+    Vote(NetStream),
+
+    // every `window` rounds a wave travels along the line, from the left end to the 
+    // right end carrying whether every node so far was quiet for the whole window, 
+    // and back from the right end carrying the verdict
+    Wave { window: u32, quiet_rounds: u32 },
+
+    // async execution has no rounds, the distributor detects when the whole 
+    // line ran out of work from the message counters reported on this stream
+    Quiescence(NetStream),
+}
+
+impl Termination {
+    // called at the end of every round, returns true if sorting should stop
+    // `control_msgs` counts the messages sent only to detect termination
+    pub fn round_done(&mut self, changed: bool, rounds_used: u32, rel_pos: RelativePos,
+                      left_link: Option<&mut Link>, right_link: Option<&mut Link>,
+                      control_msgs: &mut u32) -> Result<bool, NodeError> {
+        match self {
+            Termination::Fixed | Termination::Quiescence(_) => Ok(false),
+
+            Termination::Vote(stream) => {
+                *control_msgs += 1;
+                Self::vote(stream, changed, [left_link, right_link])
+            },
+
+            Termination::Wave { window, quiet_rounds } => {
+                *quiet_rounds = if changed { 0 } else { *quiet_rounds + 1 };
+                if !rounds_used.is_multiple_of(*window) {
+                    return Ok(false);
+                }
+                Self::wave(*quiet_rounds >= *window, rel_pos, left_link, right_link, control_msgs)
+            },
+        }
+    }
+
+    // a neighbour may still be waiting on a message of this round to be resent before
+    // it can vote, so the links are kept going until the verdict comes
+    fn vote(stream: &mut NetStream, changed: bool, mut links: [Option<&mut Link>; 2]) -> Result<bool, NodeError> {
+        let lost = |e: io::Error| {
+            log!("Lost the distributor while voting : {}", e);
+            NodeError::DistributorLost
+        };
+        stream.write_all(&[CommFlags::Vote as u8, changed as u8]).map_err(lost)?;
+
+        let mut reader = MsgReader::new(2);
+        stream.set_read_timeout(Some(VERDICT_WAIT)).map_err(lost)?;
+        let verdict = loop {
+            if let Some(verdict) = reader.poll(stream).map_err(lost)? {
+                break verdict;
+            }
+            for link in links.iter_mut().flatten() {
+                link.idle()?;
+            }
+        };
+        stream.set_read_timeout(None).map_err(lost)?;
+
+        if verdict[0] != CommFlags::Verdict as u8 {
+            log!("Expected a verdict from the distributor, got {:?}", verdict);
+            return Err(NodeError::DistributorLost);
+        }
+        Ok(verdict[1] == 1)
+    }
+}
+
+impl Termination {
+    fn send_token(link: &mut Link, quiet: bool, control_msgs: &mut u32) -> Result<(), NodeError> {
+        link.send(CommFlags::Token, &quiet)?;
+        *control_msgs += 1;
+        Ok(())
+    }
+
+    fn receive_token(link: &mut Link) -> Result<bool, NodeError> {
+        link.recv(CommFlags::Token)
+    }
+
+    // convergecast to the right end followed by a broadcast back to the left end
+    fn wave(quiet: bool, rel_pos: RelativePos, left_link: Option<&mut Link>, 
+            right_link: Option<&mut Link>, control_msgs: &mut u32) -> Result<bool, NodeError> {
+        match (rel_pos, left_link, right_link) {
+            (RelativePos::Left, None, Some(right)) => {
+                Self::send_token(right, quiet, control_msgs)?;
+                Self::receive_token(right)
+            },
+
+            (RelativePos::Middle, Some(left), Some(right)) => {
+                let quiet = Self::receive_token(left)? && quiet;
+                Self::send_token(right, quiet, control_msgs)?;
+
+                let done = Self::receive_token(right)?;
+                Self::send_token(left, done, control_msgs)?;
+                Ok(done)
+            },
+
+            (RelativePos::Right, Some(left), None) => {
+                let done = Self::receive_token(left)? && quiet;
+                Self::send_token(left, done, control_msgs)?;
+                Ok(done)
+            },
+
+            (pos, _, _) => panic!("Links do not match the position {:?}", pos),
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use std::fmt;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::time::Duration;
+
+use utility::log;
+use utility::tls::NetStream;
+
+// What the links between neighbours run over. A channel carries whole messages
+// in order, the algorithms never see how they are framed or where they go :
+// TCP (optionally over TLS) or Unix sockets between node processes, in-memory queues between threads
+// (e.g. to run an algorithm in a test) and faults.rs wraps any of them to inject faults.
+// ChecksumChannel wraps any of them to catch corrupted messages and reliable.rs
+// to resend the lost ones.
+//
+// A failed recv is ErrorKind::UnexpectedEof once the other end is gone and
+// WouldBlock / TimedOut once the timeout ran out and InvalidData for a message
+// refused as corrupted or from another run, see NodeError::from_io
+
+pub trait Channel: fmt::Debug + Send {
+    // sends one whole message
+    fn send(&mut self, msg: &[u8]) -> io::Result<()>;
+
+    // waits for the next message
+    fn recv(&mut self) -> io::Result<Vec<u8>>;
+
+    // the next message if it has fully arrived, without waiting
+    fn try_recv(&mut self) -> io::Result<Option<Vec<u8>>>;
+
+    // how long recv waits for a message, None waits for ever
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()>;
+
+    // called once the node is done with the link, for whatever it still has to get through
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    // called while the node waits on something else, to keep the link going
+    // without handing any message over
+    fn idle(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    // messages sent again because they were not acknowledged in time, see ReliableChannel
+    fn resent(&self) -> u32 {
+        0
+    }
+}
+
+// Values carried by the messages between neighbours, after the flag
+pub trait Wire: Sized {
+    fn encode(&self, buffer: &mut Vec<u8>);
+
+    // None if the bytes do not hold exactly one value
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+impl Wire for i32 {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> Option<i32> {
+        Some(i32::from_le_bytes(bytes.try_into().ok()?))
+    }
+}
+
+impl Wire for bool {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        buffer.push(*self as u8);
+    }
+
+    fn decode(bytes: &[u8]) -> Option<bool> {
+        match bytes {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        }
+    }
+}
+
+// Byte streams a StreamChannel runs over
+pub trait Stream: Read + Write + fmt::Debug + Send {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+
+impl Stream for TcpStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+
+impl Stream for NetStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        NetStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        NetStream::set_read_timeout(self, timeout)
+    }
+}
+
+#[cfg(unix)]
+impl Stream for UnixStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        UnixStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+}
+
+// Messages over a pair of byte streams, one written and one read. Each one is
+// framed as its length (u8) and the run token (u64) followed by its bytes,
+// frames with another token are refused.
+// Both may be the same connection, cloned
+#[derive(Debug)]
+pub struct StreamChannel<S: Stream> {
+    write       : S,
+    read        : S,
+    token       : u64,
+    pending     : Vec<u8>,   // frame read in part by try_recv
+    nonblocking : bool,
+}
+
+// length + token
+const FRAME_HEADER : usize = 9;
+
+pub type TcpChannel = StreamChannel<TcpStream>;
+// TCP in the clear or over TLS, what node processes use
+pub type NetChannel = StreamChannel<NetStream>;
+#[cfg(unix)]
+pub type UnixChannel = StreamChannel<UnixStream>;
+
+impl<S: Stream> StreamChannel<S> {
+    pub fn new(write: S, read: S) -> StreamChannel<S> {
+        Self::with_token(write, read, 0)
+    }
+
+    // both ends have to be given the same token
+    pub fn with_token(write: S, read: S, token: u64) -> StreamChannel<S> {
+        StreamChannel { write, read, token, pending: Vec::new(), nonblocking: false }
+    }
+
+    // the read stream stays in the mode it was last used in
+    fn set_mode(&mut self, nonblocking: bool) -> io::Result<()> {
+        if self.nonblocking != nonblocking {
+            self.read.set_nonblocking(nonblocking)?;
+            self.nonblocking = nonblocking;
+        }
+        Ok(())
+    }
+
+    // reads what is missing from the next frame, returns the message once it is whole
+    fn fill(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let size = match self.pending.first() {
+            Some(len) => FRAME_HEADER + *len as usize,
+            None => 1,
+        };
+        let mut chunk = vec![0u8; size - self.pending.len()];
+        match self.read.read(&mut chunk)? {
+            0 => return Err(io::Error::new(ErrorKind::UnexpectedEof, "Peer disconnected")),
+            bytes_read => self.pending.extend_from_slice(&chunk[..bytes_read]),
+        }
+
+        if self.pending.len() != FRAME_HEADER + self.pending[0] as usize {
+            return Ok(None);
+        }
+        let frame: Vec<u8> = self.pending.drain(..).collect();
+        let token = u64::from_le_bytes(frame[1..FRAME_HEADER].try_into().unwrap());
+        if token != self.token {
+            eprintln!("Refused a frame with run token {} : {:?}", token, &frame[FRAME_HEADER..]);
+            return Err(io::Error::new(ErrorKind::InvalidData, "Frame with another run token"));
+        }
+        Ok(Some(frame[FRAME_HEADER..].to_vec()))
+    }
+}
+
+impl<S: Stream> Channel for StreamChannel<S> {
+    fn send(&mut self, msg: &[u8]) -> io::Result<()> {
+        let len = u8::try_from(msg.len())
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "Message too long for a frame"))?;
+        let mut frame = Vec::with_capacity(FRAME_HEADER + msg.len());
+        frame.push(len);
+        frame.extend_from_slice(&self.token.to_le_bytes());
+        frame.extend_from_slice(msg);
+        self.write.write_all(&frame)
+    }
+
+    fn recv(&mut self) -> io::Result<Vec<u8>> {
+        self.set_mode(false)?;
+        loop {
+            if let Some(msg) = self.fill()? {
+                return Ok(msg);
+            }
+        }
+    }
+
+    fn try_recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.set_mode(true)?;
+        loop {
+            match self.fill() {
+                Ok(Some(msg)) => return Ok(Some(msg)),
+                Ok(None) => (),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.read.set_read_timeout(timeout)
+    }
+}
+
+// Adds a CRC32 to every message sent over the wrapped channel and checks it on
+// every one received, a message that fails it is refused with ErrorKind::InvalidData.
+// Wraps FaultyChannel so the corruption it injects is caught like one on the wire
+#[derive(Debug)]
+pub struct ChecksumChannel {
+    inner : Box<dyn Channel>,
+}
+
+impl ChecksumChannel {
+    pub fn wrap(inner: Box<dyn Channel>) -> Box<dyn Channel> {
+        Box::new(ChecksumChannel { inner })
+    }
+
+    fn check(msg: Vec<u8>) -> io::Result<Vec<u8>> {
+        let Some(split) = msg.len().checked_sub(4) else {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Message too short for its checksum"));
+        };
+        let (payload, crc) = msg.split_at(split);
+        if crc32fast::hash(payload).to_le_bytes() != crc {
+            log!("Refused a message failing its checksum : {:?}", msg);
+            return Err(io::Error::new(ErrorKind::InvalidData, "Message failed its checksum"));
+        }
+        Ok(payload.to_vec())
+    }
+}
+
+impl Channel for ChecksumChannel {
+    fn send(&mut self, msg: &[u8]) -> io::Result<()> {
+        let mut msg = msg.to_vec();
+        msg.extend_from_slice(&crc32fast::hash(&msg).to_le_bytes());
+        self.inner.send(&msg)
+    }
+
+    fn recv(&mut self) -> io::Result<Vec<u8>> {
+        Self::check(self.inner.recv()?)
+    }
+
+    fn try_recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.inner.try_recv()?.map(Self::check).transpose()
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner.set_timeout(timeout)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    fn idle(&mut self) -> io::Result<()> {
+        self.inner.idle()
+    }
+
+    fn resent(&self) -> u32 {
+        self.inner.resent()
+    }
+}
+
+// Messages between two ends living in the same process, made by MemChannel::pair
+#[derive(Debug)]
+pub struct MemChannel {
+    tx      : Sender<Vec<u8>>,
+    rx      : Receiver<Vec<u8>>,
+    timeout : Option<Duration>,
+}
+
+fn disconnected() -> io::Error {
+    io::Error::new(ErrorKind::UnexpectedEof, "Peer disconnected")
+}
+
+impl MemChannel {
+    // both ends of a link, what one sends the other receives
+    pub fn pair() -> (MemChannel, MemChannel) {
+        let (a_tx, b_rx) = channel();
+        let (b_tx, a_rx) = channel();
+        (MemChannel { tx: a_tx, rx: a_rx, timeout: None },
+         MemChannel { tx: b_tx, rx: b_rx, timeout: None })
+    }
+}
+
+impl Channel for MemChannel {
+    fn send(&mut self, msg: &[u8]) -> io::Result<()> {
+        self.tx.send(msg.to_vec())
+            .map_err(|_| io::Error::new(ErrorKind::BrokenPipe, "Peer disconnected"))
+    }
+
+    fn recv(&mut self) -> io::Result<Vec<u8>> {
+        let Some(timeout) = self.timeout else {
+            return self.rx.recv().map_err(|_| disconnected());
+        };
+        self.rx.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => io::Error::new(ErrorKind::TimedOut, "No message in time"),
+            RecvTimeoutError::Disconnected => disconnected(),
+        })
+    }
+
+    fn try_recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        match self.rx.try_recv() {
+            Ok(msg) => Ok(Some(msg)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(disconnected()),
+        }
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use utility::{CommFlags, PartialOrder};
+
+    use super::*;
+    use crate::{Link, NodeContext, NodeError, OddEven, RelativePos, SortAlgorithm};
+
+    fn linked_pair() -> (Link, Link) {
+        let (a, b) = MemChannel::pair();
+        (Link::new(Box::new(a), RelativePos::Right), Link::new(Box::new(b), RelativePos::Left))
+    }
+
+    // the example of the README
+    #[test]
+    fn odd_even_sorts_two_threads() {
+        let (left, right) = linked_pair();
+        let rounds = OddEven.rounds(2);
+        let node = thread::spawn(move || {
+            let mut ctx = NodeContext::new(3, 2, 2, rounds, PartialOrder::LessThan, Some(right), None);
+            OddEven.run(&mut ctx).unwrap()
+        });
+        let mut ctx = NodeContext::new(7, 1, 2, rounds, PartialOrder::LessThan, None, Some(left));
+        assert_eq!((OddEven.run(&mut ctx).unwrap(), node.join().unwrap()), (3, 7));
+    }
+
+    #[test]
+    fn typed_values_go_through() {
+        let (mut left, mut right) = linked_pair();
+        left.send(CommFlags::Exchange, &-42).unwrap();
+        left.send(CommFlags::Token, &true).unwrap();
+        assert_eq!(right.recv::<i32>(CommFlags::Exchange).unwrap(), -42);
+        assert!(right.recv::<bool>(CommFlags::Token).unwrap());
+    }
+
+    #[test]
+    fn an_unexpected_message_is_a_protocol_error() {
+        let (mut left, mut right) = linked_pair();
+        left.send(CommFlags::Token, &true).unwrap();
+        assert!(matches!(right.recv::<i32>(CommFlags::Exchange), Err(NodeError::Protocol(RelativePos::Left))));
+
+        left.send(CommFlags::Exchange, &true).unwrap();
+        let malformed = right.try_recv::<i32>().unwrap_err();
+        assert_eq!(malformed.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn a_peer_gone_or_silent_is_told_apart() {
+        let (mut left, right) = linked_pair();
+        left.channel.set_timeout(Some(Duration::from_millis(10))).unwrap();
+        assert!(matches!(left.recv::<i32>(CommFlags::Exchange), Err(NodeError::Timeout(RelativePos::Right))));
+
+        drop(right);
+        assert!(matches!(left.recv::<i32>(CommFlags::Exchange), Err(NodeError::NeighbourLost(RelativePos::Right))));
+        assert!(matches!(left.send(CommFlags::Exchange, &1), Err(NodeError::NeighbourLost(RelativePos::Right))));
+    }
+
+    #[test]
+    fn checksum_refuses_a_corrupted_message() {
+        let (a, mut b) = MemChannel::pair();
+        let mut sender = ChecksumChannel::wrap(Box::new(a));
+        sender.send(&[1, 2, 3]).unwrap();
+        // what went over the wire, then the same with a bit flipped on the way
+        let mut msg = b.recv().unwrap();
+        assert_eq!(ChecksumChannel::check(msg.clone()).unwrap(), [1, 2, 3]);
+        msg[1] ^= 4;
+        assert_eq!(ChecksumChannel::check(msg).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
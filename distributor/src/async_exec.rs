@@ -0,0 +1,136 @@
+use std::io::Write;
+use std::thread::sleep;
+
+use utility::{CommFlags, MsgReader, ABORT_MSG, COUNTER_MSG, IDLE_WAIT};
+
+use crate::{parse_abort, Failure, Node};
+
+fn msg_size(flag: u8) -> usize {
+    if flag == CommFlags::Abort as u8 { ABORT_MSG } else { COUNTER_MSG }
+}
+
+// reads the next message of a node, marking the node failed if it aborted or crashed
+fn poll_node(reader: &mut MsgReader, node: &mut Node) -> Option<Vec<u8>> {
+    match reader.poll(&mut node.stream) {
+        Ok(Some(msg)) if msg[0] == CommFlags::Abort as u8 => {
+            node.failure = Some(parse_abort(&msg[1..]));
+            None
+        },
+        Ok(msg) => msg,
+        Err(_) => {
+            node.failure = Some(Failure::Crashed);
+            None
+        },
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Counters {
+    idle     : bool,
+    sent     : u32,
+    received : u32,
+}
+
+fn parse_counters(msg: &[u8]) -> Counters {
+    Counters {
+        idle     : msg[1] == 1,
+        sent     : u32::from_le_bytes(msg[2..6].try_into().unwrap()),
+        received : u32::from_le_bytes(msg[6..10].try_into().unwrap()),
+    }
+}
+
+// every node idle and no neighbour message still in flight
+fn looks_done(counters: &[Option<Counters>]) -> bool {
+    let (mut sent, mut received) = (0u64, 0u64);
+    for node in counters {
+        match node {
+            Some(node) if node.idle => {
+                sent += node.sent as u64;
+                received += node.received as u64;
+            },
+            _ => return false,
+        }
+    }
+    sent == received
+}
+
+// Detects when the nodes of an async run ran out of work and stops them.
+// Nodes report their message counters whenever they become idle. Once the latest
+// reports look done a Probe collects the counters a second time, the run is over
+// only if nothing changed in between (four counter method), then Stop is sent.
+// A node failing (faults injected) ends the run right away, the rest are stopped.
+pub fn coordinate_async(node_data: &mut [Node]) {
+    let mut readers: Vec<MsgReader> = node_data.iter().map(|_| MsgReader::by_flag(msg_size)).collect();
+    let mut latest: Vec<Option<Counters>> = vec![None; node_data.len()];
+    let mut last_probed = None;
+
+    for node in node_data.iter_mut() {
+        node.stream.set_nonblocking(true).expect("Failed to set non-blocking");
+    }
+
+    'run: loop {
+        let mut progressed = false;
+        for (i, node) in node_data.iter_mut().enumerate() {
+            if let Some(msg) = poll_node(&mut readers[i], node) {
+                progressed = true;
+                assert_eq!(msg[0], CommFlags::Idle as u8);
+                latest[i] = Some(parse_counters(&msg));
+            }
+        }
+        if node_data.iter().any(|node| node.failure.is_some()) {
+            break;
+        }
+
+        // a node busy at the last probe reports again before it can be idle for good
+        if looks_done(&latest) && last_probed.as_ref() != Some(&latest) {
+            let snapshot = latest.clone();
+            let mut status: Vec<Option<Counters>> = vec![None; node_data.len()];
+
+            for node in node_data.iter_mut() {
+                node.stream.write_all(&[CommFlags::Probe as u8]).expect("Failed to send probe");
+            }
+
+            // idle reports sent before the probe was seen still arrive first
+            while status.iter().any(|node| node.is_none()) {
+                let mut received = false;
+                for (i, node) in node_data.iter_mut().enumerate() {
+                    if let Some(msg) = poll_node(&mut readers[i], node) {
+                        received = true;
+                        match msg[0] {
+                            flag if flag == CommFlags::Idle as u8   => latest[i] = Some(parse_counters(&msg)),
+                            flag if flag == CommFlags::Status as u8 => status[i] = Some(parse_counters(&msg)),
+                            def_val => panic!("Unexpected message from node : {}", def_val),
+                        }
+                    }
+                }
+                if node_data.iter().any(|node| node.failure.is_some()) {
+                    break 'run;
+                }
+                if !received {
+                    sleep(IDLE_WAIT);
+                }
+            }
+
+            if status == snapshot {
+                break;
+            }
+            last_probed = Some(snapshot);
+            continue;
+        }
+
+        if !progressed {
+            sleep(IDLE_WAIT);
+        }
+    }
+
+    for (i, node) in node_data.iter_mut().enumerate().filter(|(_, node)| node.failure.is_none()) {
+        // stopped early by a failure, leave the stream at a message boundary
+        // counters sent before the node saw Stop are skipped with its output
+        while readers[i].is_partial() && node.failure.is_none() {
+            poll_node(&mut readers[i], node);
+        }
+        // a node failing right now shows up when its output is read
+        let _ = node.stream.write_all(&[CommFlags::Stop as u8]);
+        node.stream.set_nonblocking(false).expect("Failed to set blocking");
+    }
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::io::{self, Read, Write};
+
+use clap::ValueEnum;
+
+// Formats of the numbers read with --input and written with --output
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Lines,    // one number per line
+    Csv,      // separated by commas and / or whitespace
+    Binary,   // little-endian i32 array
+}
+
+// "-" stands for stdin / stdout
+pub const STD_STREAM : &str = "-";
+
+// position is the line for Lines, the no.of the number for Csv
+fn parse_num(token: &str, position: usize) -> Result<i32, String> {
+    token.parse::<i32>().map_err(|e| format!("Invalid number '{}' at {} : {}", token, position, e))
+}
+
+// parses numbers separated by commas and / or whitespace
+pub fn parse_text(text: &str) -> Result<Vec<i32>, String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .enumerate()
+        .map(|(i, token)| parse_num(token, i + 1))
+        .collect()
+}
+
+pub fn parse(data: &[u8], format: Format) -> Result<Vec<i32>, String> {
+    match format {
+        Format::Lines => {
+            let text = std::str::from_utf8(data).map_err(|e| format!("Input is not text : {}", e))?;
+            text.lines().enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(i, line)| parse_num(line.trim(), i + 1))
+                .collect()
+        },
+        Format::Csv => parse_text(std::str::from_utf8(data).map_err(|e| format!("Input is not text : {}", e))?),
+        Format::Binary => {
+            if !data.len().is_multiple_of(4) {
+                return Err(format!("Binary input of {} bytes is not a whole no.of i32", data.len()));
+            }
+            Ok(data.chunks_exact(4).map(|num| i32::from_le_bytes(num.try_into().unwrap())).collect())
+        },
+    }
+}
+
+pub fn serialise(nums: &[i32], format: Format) -> Vec<u8> {
+    match format {
+        Format::Lines => nums.iter().map(|num| format!("{}\n", num)).collect::<String>().into_bytes(),
+        Format::Csv => {
+            let line = nums.iter().map(|num| num.to_string()).collect::<Vec<String>>().join(",");
+            format!("{}\n", line).into_bytes()
+        },
+        Format::Binary => nums.iter().flat_map(|num| num.to_le_bytes()).collect(),
+    }
+}
+
+// reads the numbers from a file, or stdin for "-"
+pub fn read_nums(path: &str, format: Format) -> Vec<i32> {
+    let data = if path == STD_STREAM {
+        let mut data = Vec::new();
+        io::stdin().read_to_end(&mut data).expect("Failed to read stdin");
+        data
+    } else {
+        fs::read(path).unwrap_or_else(|e| panic!("Failed to read {} : {}", path, e))
+    };
+    parse(&data, format).unwrap_or_else(|e| panic!("Failed to parse {} : {}", path, e))
+}
+
+// writes the numbers to a file, or stdout for "-"
+pub fn write_nums(path: &str, nums: &[i32], format: Format) {
+    let data = serialise(nums, format);
+    if path == STD_STREAM {
+        io::stdout().write_all(&data).expect("Failed to write stdout");
+    } else {
+        fs::write(path, data).unwrap_or_else(|e| panic!("Failed to write {} : {}", path, e));
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use algorithms::SortAlgorithm;
+
+const MARGIN   : usize = 70;   // space on the left for the position labels
+const TOP      : usize = 50;   // space on top for the round labels
+const COL_W    : usize = 70;   // width of one round
+const ROW_H    : usize = 32;   // distance between two wires
+
+// Renders the comparator network executed by `algo` on a line of `no_nodes` nodes.
+// `values[r][i]` is the value held by node i+1 after round r, as traced by the nodes;
+// round 0 is the input. Without values only the network is drawn.
+pub fn render_svg(algo: &dyn SortAlgorithm, no_nodes: u32, values: Option<&[Vec<i32>]>) -> String {
+    let rounds = algo.rounds(no_nodes) as usize;
+    let width  = MARGIN + (rounds + 1) * COL_W;
+    let height = TOP + no_nodes as usize * ROW_H;
+    let wire_y = |glb_pos: u32| TOP + (glb_pos as usize - 1) * ROW_H + ROW_H / 2;
+
+    let mut svg = String::new();
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+                   font-family=\"monospace\" font-size=\"11\">", width, height).unwrap();
+    writeln!(svg, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>").unwrap();
+
+    // wires, one per node
+    for glb_pos in 1..=no_nodes {
+        let y = wire_y(glb_pos);
+        writeln!(svg, "<text x=\"8\" y=\"{}\">P{}</text>", y + 4, glb_pos).unwrap();
+        writeln!(svg, "<line x1=\"{}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\" stroke=\"#999\"/>",
+                 MARGIN, width - 10).unwrap();
+    }
+
+    for round in 0..rounds {
+        let x = MARGIN + (round + 1) * COL_W - COL_W / 2;
+        writeln!(svg, "<text x=\"{}\" y=\"20\" text-anchor=\"middle\">R{}</text>", x, round + 1).unwrap();
+
+        // comparators of the round
+        for group in algo.comparators(no_nodes, round as u32) {
+            writeln!(svg, "<line x1=\"{x}\" y1=\"{}\" x2=\"{x}\" y2=\"{}\" stroke=\"#1f5fbf\" \
+                           stroke-width=\"2\"/>", wire_y(group.first), wire_y(group.last)).unwrap();
+            for glb_pos in group.first..=group.last {
+                writeln!(svg, "<circle cx=\"{x}\" cy=\"{}\" r=\"4\" fill=\"#1f5fbf\"/>",
+                         wire_y(glb_pos)).unwrap();
+            }
+        }
+    }
+
+    // values held on every wire, changed ones are highlighted
+    if let Some(values) = values {
+        for (round, row) in values.iter().enumerate() {
+            let x = MARGIN + round * COL_W + 4;
+            for (i, num) in row.iter().enumerate() {
+                let changed = round > 0 && values[round - 1][i] != *num;
+                let colour = if changed { "#c0392b" } else { "#222" };
+                writeln!(svg, "<text x=\"{}\" y=\"{}\" fill=\"{}\">{}</text>",
+                         x, wire_y(i as u32 + 1) - 5, colour, num).unwrap();
+            }
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Same diagram wrapped in a self-contained html page
+pub fn render_html(algo: &dyn SortAlgorithm, no_nodes: u32, values: Option<&[Vec<i32>]>) -> String {
+    format!("<!DOCTYPE html>\n\
+             <html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{name} on {no_nodes} nodes</title>\n</head>\n\
+             <body style=\"font-family: sans-serif\">\n\
+             <h2>{name} on a line of {no_nodes} nodes ({rounds} rounds)</h2>\n\
+             <p>Each horizontal wire is a node (P1 is the left end). \
+             A vertical bar joins the nodes compared with each other in that round. \
+             Values are the ones held after each round, changes are shown in red.</p>\n\
+             {svg}</body>\n</html>\n",
+             name = algo.name(),
+             rounds = algo.rounds(no_nodes),
+             svg = render_svg(algo, no_nodes, values))
+}
+
+// Writes the diagram to `path`, html if the extension is .html/.htm else svg
+pub fn write_diagram(path: &str, algo: &dyn SortAlgorithm, no_nodes: u32, values: Option<&[Vec<i32>]>) {
+    let is_html = matches!(Path::new(path).extension().and_then(|ext| ext.to_str()),
+                           Some("html") | Some("htm"));
+    let content = if is_html {
+        render_html(algo, no_nodes, values)
+    } else {
+        render_svg(algo, no_nodes, values)
+    };
+    fs::write(path, content).unwrap_or_else(|e| panic!("Failed to write diagram to {} : {}", path, e));
+}
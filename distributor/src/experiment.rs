@@ -0,0 +1,316 @@
+use std::fs;
+use std::time::Instant;
+
+use clap::ValueEnum;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+use algorithms::AlgoEntry;
+use utility::{PartialOrder, OPT_CHECKSUM, OPT_DUPLEX, OPT_POOL, OPT_RELIABLE};
+
+use crate::inputs::{self, parse_distribution, Distribution};
+use crate::{check_algo, finished_nums, invalid, parse_algo, parse_order, parse_topology, run_nodes, run_on_pool,
+            verify_results, NodeFaults, Pool, RunConfig, RunOutcome};
+
+// Runs every combination of a sweep and reports one row per run
+
+#[derive(Clone, Debug)]
+pub struct Sizes(Vec<u32>);
+
+// parses sizes given as a comma separated list of N or FROM..TO[:STEP] (TO excluded)
+// e.g. `5,10..2000:100`
+fn parse_sizes(inp_str: &str) -> Result<Sizes, String> {
+    let parse = |num: &str| num.trim().parse::<u32>().map_err(|e| format!("Invalid size '{}' : {}", num, e));
+    let mut sizes = Vec::new();
+
+    for item in inp_str.split(',') {
+        match item.split_once("..") {
+            Some((from, rest)) => {
+                let (to, step) = match rest.split_once(':') {
+                    Some((to, step)) => (parse(to)?, parse(step)?),
+                    None => (parse(rest)?, 1),
+                };
+                if step == 0 {
+                    return Err("Step has to be above 0".to_string());
+                }
+                sizes.extend((parse(from)?..to).step_by(step as usize));
+            },
+            None => sizes.push(parse(item)?),
+        }
+    }
+
+    if sizes.is_empty() {
+        return Err(format!("No sizes in '{}'", inp_str));
+    }
+    Ok(Sizes(sizes))
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ExperimentArgs {
+    #[arg(long,
+        default_value = "odd-even,sasaki,triplet",
+        value_delimiter = ',',
+        value_parser = parse_algo,
+        help = "Algorithms to run, as named by list-algos",
+    )]
+    algos : Vec<&'static AlgoEntry>,
+
+    #[arg(long,
+        default_value = "asc",
+        value_delimiter = ',',
+        value_parser = parse_order,
+        help = "Partial orders to run, asc and / or desc",
+    )]
+    orders : Vec<PartialOrder>,
+
+    #[arg(long,
+        default_value = "10..100:10",
+        value_parser = parse_sizes,
+        help = "No.of nodes, N or FROM..TO[:STEP] (TO excluded), comma separated\n\
+                e.g. `--sizes 5,10..2000:100`",
+    )]
+    sizes : Sizes,
+
+    #[arg(long,
+        default_value = "uniform",
+        value_delimiter = ',',
+        value_parser = parse_distribution,
+        help = "Input distributions, as given to --distribution, comma separated",
+    )]
+    distributions : Vec<Distribution>,
+
+    #[arg(long,
+        default_value = "line",
+        value_delimiter = ',',
+        value_parser = parse_topology,
+        help = "Topologies of the network, only line for now",
+    )]
+    topologies : Vec<String>,
+
+    #[arg(long,
+        default_value_t = 1,
+        help = "Runs of every configuration, each with its own input",
+    )]
+    repetitions : u16,
+
+    #[arg(long,
+        help = "Seed of the sweep, run i uses seed + i. Picked and printed when not given",
+    )]
+    seed : Option<u64>,
+
+    #[arg(long,
+        value_enum,
+        default_value_t = ReportFormat::Csv,
+        help = "Format of the rows",
+    )]
+    format : ReportFormat,
+
+    #[arg(long,
+        help = "Write the rows to this file instead of stdout",
+    )]
+    out : Option<String>,
+
+    #[arg(long,
+        help = "Spawn the nodes once and reuse them across runs, respawned after a failed run",
+    )]
+    pool : bool,
+
+    #[arg(long,
+        help = "One full-duplex connection per pair of neighbours instead of one each way",
+    )]
+    duplex : bool,
+
+    #[arg(long,
+        help = "TLS on every connection, with a CA made for each set of spawned nodes",
+    )]
+    tls : bool,
+
+    #[arg(long,
+        help = "CRC32 on every message between neighbours",
+    )]
+    checksum : bool,
+
+    #[arg(long,
+        help = "Acknowledge and resend the messages between neighbours, see `run --reliable`",
+    )]
+    reliable : bool,
+}
+
+// One run of the sweep
+#[derive(Serialize, Debug)]
+struct Row {
+    run           : usize,
+    algo          : String,
+    partial_order : String,   // asc | desc
+    size          : u32,
+    distribution  : String,
+    topology      : String,
+    tls           : bool,
+    repetition    : u16,
+    seed          : u64,
+    rounds_used   : u32,
+    rounds        : u32,
+    setup_ms      : f64,
+    sort_ms       : f64,
+    tls_ms        : f64,
+    neighbour_tls_ms : f64,
+    exchange_msgs : u64,
+    control_msgs  : u64,
+    resent_msgs   : u64,
+    passed        : bool,
+}
+
+const CSV_HEADER : &str = "run,algo,partial_order,size,distribution,topology,tls,repetition,seed,\
+                           rounds_used,rounds,setup_ms,sort_ms,tls_ms,neighbour_tls_ms,exchange_msgs,control_msgs,resent_msgs,passed";
+
+impl Row {
+    fn to_csv(&self) -> String {
+        format!("{},{},{},{},{},{},{},{},{},{},{},{:.3},{:.3},{:.3},{:.3},{},{},{},{}",
+                self.run, self.algo, self.partial_order, self.size, self.distribution,
+                self.topology, self.tls, self.repetition, self.seed, self.rounds_used, self.rounds, self.setup_ms,
+                self.sort_ms, self.tls_ms, self.neighbour_tls_ms, self.exchange_msgs, self.control_msgs, self.resent_msgs,
+                self.passed)
+    }
+}
+
+// One combination of the sweep
+struct Point<'a> {
+    algo          : &'static AlgoEntry,
+    partial_order : PartialOrder,
+    size          : u32,
+    distribution  : Distribution,
+    topology      : &'a str,
+    tls           : bool,
+    repetition    : u16,
+}
+
+// runs on the pool when one is given, otherwise on nodes spawned for the run
+fn run_one(run: usize, seed: u64, point: Point, options: u8, pool: Option<&mut Pool>) -> Row {
+    let Point { algo, partial_order, size, distribution, topology, tls, repetition } = point;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let input_nums = inputs::generate(distribution, size, (1, size.min(i32::MAX as u32) as i32), &mut rng);
+
+    let algorithm = algo.construct();
+    let rounds = algorithm.rounds(size);
+    let config = RunConfig {
+        algo        : algorithm,
+        partial_order,
+        options     : if pool.is_some() { options | OPT_POOL } else { options },
+        node_faults : vec![NodeFaults::default(); size as usize],
+        faults      : None,
+        seed,
+        tls,
+    };
+    let outcome = match pool {
+        Some(pool) => run_on_pool(pool, &config, &input_nums, false, Instant::now()),
+        None => run_nodes(&config, &input_nums, false),
+    };
+    let RunOutcome { output_nums, stats, timings, .. } = outcome;
+
+    Row {
+        run,
+        algo          : algo.name().to_string(),
+        partial_order : partial_order.name().to_string(),
+        size,
+        distribution  : distribution.to_string(),
+        topology      : topology.to_string(),
+        tls,
+        repetition,
+        seed,
+        rounds_used   : stats.rounds_used,
+        rounds,
+        setup_ms      : timings.setup_ms,
+        sort_ms       : timings.sort_ms,
+        tls_ms        : timings.tls_ms,
+        neighbour_tls_ms : timings.neighbour_tls_ms,
+        exchange_msgs : stats.exchange_msgs,
+        control_msgs  : stats.control_msgs,
+        resent_msgs   : stats.resent_msgs,
+        passed        : finished_nums(&output_nums).is_some_and(|nums| verify_results(&input_nums, &nums, partial_order)),
+    }
+}
+
+// Runs the whole sweep, progress goes to stderr so the rows can be piped
+pub fn run_experiment(args: &ExperimentArgs) {
+    // every combination is checked before the first run
+    for topology in &args.topologies {
+        for &algo in &args.algos {
+            for &size in &args.sizes.0 {
+                check_algo(algo.construct().as_ref(), topology, size).unwrap_or_else(|e| invalid(&e));
+            }
+        }
+    }
+
+    let base_seed = args.seed.unwrap_or_else(rand::random);
+    eprintln!("Seed          : {}", base_seed);
+
+    let total = args.algos.len() * args.orders.len() * args.sizes.0.len() * args.distributions.len() *
+                args.topologies.len() * args.repetitions as usize;
+    let mut rows = Vec::with_capacity(total);
+    let pool_size = *args.sizes.0.iter().max().unwrap();
+    let mut options = if args.duplex { OPT_DUPLEX } else { 0 };
+    if args.checksum {
+        options |= OPT_CHECKSUM;
+    }
+    if args.reliable {
+        options |= OPT_RELIABLE;
+    }
+    let mut pool: Option<Pool> = None;
+
+    for topology in &args.topologies {
+        for &algo in &args.algos {
+            for &partial_order in &args.orders {
+                for &size in &args.sizes.0 {
+                    for &distribution in &args.distributions {
+                        for repetition in 1..=args.repetitions {
+                            let run = rows.len() + 1;
+                            let seed = base_seed.wrapping_add(run as u64);
+                            let point = Point { algo, partial_order, size, distribution, topology, tls: args.tls, repetition };
+                            if args.pool && pool.is_none() {
+                                pool = Some(Pool::spawn(pool_size, base_seed, None, args.tls, false));
+                            }
+                            let row = run_one(run, seed, point, options, pool.as_mut());
+                            // nodes of a failed run may be gone or out of step, start afresh
+                            if !row.passed {
+                                if let Some(pool) = pool.take() {
+                                    pool.close();
+                                }
+                            }
+                            eprintln!("[{}/{}] {} {} n={} {} #{} : {}", run, total, row.algo,
+                                      partial_order.name(), size, distribution, repetition,
+                                      if row.passed { "passed" } else { "FAILED" });
+                            rows.push(row);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(pool) = pool {
+        pool.close();
+    }
+
+    let report = match args.format {
+        ReportFormat::Csv => {
+            let mut lines = vec![CSV_HEADER.to_string()];
+            lines.extend(rows.iter().map(|row| row.to_csv()));
+            lines.join("\n") + "\n"
+        },
+        ReportFormat::Json => serde_json::to_string_pretty(&rows).expect("Failed to serialise rows") + "\n",
+    };
+
+    match args.out.as_ref() {
+        Some(path) => {
+            fs::write(path, report).unwrap_or_else(|e| panic!("Failed to write {} : {}", path, e));
+            eprintln!("=> {} rows written to {}", rows.len(), path);
+        },
+        None => print!("{}", report),
+    }
+}
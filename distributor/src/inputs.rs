@@ -0,0 +1,182 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::Rng;
+
+// Input distributions for --test, given as name[:param]
+//     uniform                 random values over the whole range
+//     sorted / reverse        ascending / descending
+//     nearly-sorted:K         ascending with K swaps of two distinct random positions (1 by default),
+//                             at most one per value and none for less than 2 values
+//     equal                   one value repeated
+//     few-unique:K            only K distinct values (4 by default)
+//     sawtooth:P              ramps up over every P values (8 by default)
+//     organ-pipe              ramps up to the middle, then down
+//     gaussian                normal around the middle of the range, clamped to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    Uniform,
+    Sorted,
+    Reverse,
+    NearlySorted(usize),
+    Equal,
+    FewUnique(usize),
+    Sawtooth(usize),
+    OrganPipe,
+    Gaussian,
+}
+
+// parses a distribution given as name[:param]
+pub fn parse_distribution(inp_str: &str) -> Result<Distribution, String> {
+    let (name, param) = match inp_str.split_once(':') {
+        Some((name, param)) => {
+            let param = param.trim().parse::<usize>()
+                .map_err(|e| format!("Invalid parameter '{}' : {}", param, e))?;
+            (name.trim(), Some(param))
+        },
+        None => (inp_str.trim(), None),
+    };
+
+    let dist = match name {
+        "uniform"       => Distribution::Uniform,
+        "sorted"        => Distribution::Sorted,
+        "reverse"       => Distribution::Reverse,
+        "nearly-sorted" => Distribution::NearlySorted(param.unwrap_or(1)),
+        "equal"         => Distribution::Equal,
+        "few-unique"    => Distribution::FewUnique(param.unwrap_or(4)),
+        "sawtooth"      => Distribution::Sawtooth(param.unwrap_or(8)),
+        "organ-pipe"    => Distribution::OrganPipe,
+        "gaussian"      => Distribution::Gaussian,
+        def_val => return Err(format!("Unknown distribution '{}'", def_val)),
+    };
+
+    match dist {
+        Distribution::FewUnique(0) | Distribution::Sawtooth(0) => Err(format!("{} needs a parameter above 0", name)),
+        _ if param.is_some() && !matches!(dist, Distribution::NearlySorted(_) |
+                                                Distribution::FewUnique(_) | Distribution::Sawtooth(_)) =>
+            Err(format!("{} takes no parameter", name)),
+        dist => Ok(dist),
+    }
+}
+
+// parses a value range given as lo:hi
+pub fn parse_range(inp_str: &str) -> Result<(i32, i32), String> {
+    // split on the last ':' so that negative bounds parse
+    let (low, high) = inp_str.rsplit_once(':')
+        .ok_or(format!("Expected lo:hi, got '{}'", inp_str))?;
+    let low = low.trim().parse::<i32>().map_err(|e| format!("Invalid bound '{}' : {}", low, e))?;
+    let high = high.trim().parse::<i32>().map_err(|e| format!("Invalid bound '{}' : {}", high, e))?;
+    if low > high {
+        return Err(format!("Empty range {}:{}", low, high));
+    }
+    Ok((low, high))
+}
+
+// value `step` of `steps` evenly spread over the range
+fn spread(range: (i32, i32), step: usize, steps: usize) -> i32 {
+    let span = range.1 as i64 - range.0 as i64;
+    let offset = if steps <= 1 { 0 } else { span * step as i64 / (steps - 1) as i64 };
+    (range.0 as i64 + offset) as i32
+}
+
+// normal sample from two uniform ones (Box-Muller)
+fn gaussian(rng: &mut StdRng, mean: f64, std_dev: f64) -> f64 {
+    let u1 = 1.0 - rng.random::<f64>();   // (0, 1], keeps ln away from 0
+    let u2 = rng.random::<f64>();
+    mean + std_dev * (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+// `count` values of the distribution, all within `range` (inclusive)
+pub fn generate(dist: Distribution, count: u32, range: (i32, i32), rng: &mut StdRng) -> Vec<i32> {
+    let count = count as usize;
+    let mut uniform = |count: usize| -> Vec<i32> {
+        (0..count).map(|_| rng.random_range(range.0..=range.1)).collect()
+    };
+
+    match dist {
+        Distribution::Uniform => uniform(count),
+        Distribution::Sorted => {
+            let mut nums = uniform(count);
+            nums.sort();
+            nums
+        },
+        Distribution::Reverse => {
+            let mut nums = uniform(count);
+            nums.sort_by(|a, b| b.cmp(a));
+            nums
+        },
+        Distribution::NearlySorted(swaps) => {
+            let mut nums = uniform(count);
+            nums.sort();
+            // j is drawn among the other positions so every swap moves two values
+            let swaps = if count < 2 { 0 } else { swaps.min(count) };
+            for _ in 0..swaps {
+                let i = rng.random_range(0..count);
+                let j = (i + rng.random_range(1..count)) % count;
+                nums.swap(i, j);
+            }
+            nums
+        },
+        Distribution::Equal => vec![uniform(1)[0]; count],
+        Distribution::FewUnique(unique) => {
+            let values: Vec<i32> = (0..unique).map(|step| spread(range, step, unique)).collect();
+            (0..count).map(|_| *values.choose(rng).unwrap()).collect()
+        },
+        Distribution::Sawtooth(period) => {
+            (0..count).map(|i| spread(range, i % period, period)).collect()
+        },
+        Distribution::OrganPipe => {
+            let steps = count.div_ceil(2);
+            (0..count).map(|i| spread(range, i.min(count - 1 - i), steps)).collect()
+        },
+        Distribution::Gaussian => {
+            let mean = (range.0 as f64 + range.1 as f64) / 2.0;
+            let std_dev = (range.1 as f64 - range.0 as f64) / 6.0;
+            (0..count).map(|_| gaussian(rng, mean, std_dev).round()
+                                .clamp(range.0 as f64, range.1 as f64) as i32).collect()
+        },
+    }
+}
+
+// Serialises back into name[:param]
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Distribution::Uniform              => write!(f, "uniform"),
+            Distribution::Sorted               => write!(f, "sorted"),
+            Distribution::Reverse              => write!(f, "reverse"),
+            Distribution::NearlySorted(swaps)  => write!(f, "nearly-sorted:{}", swaps),
+            Distribution::Equal                => write!(f, "equal"),
+            Distribution::FewUnique(unique)    => write!(f, "few-unique:{}", unique),
+            Distribution::Sawtooth(period)     => write!(f, "sawtooth:{}", period),
+            Distribution::OrganPipe            => write!(f, "organ-pipe"),
+            Distribution::Gaussian             => write!(f, "gaussian"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn a_nearly_sorted_swap_moves_two_values() {
+        for seed in 0..50 {
+            let nums = generate(Distribution::NearlySorted(1), 20, (1, 1_000_000), &mut StdRng::seed_from_u64(seed));
+            let mut sorted = nums.clone();
+            sorted.sort();
+            let moved = nums.iter().zip(&sorted).filter(|(num, sorted_num)| num != sorted_num).count();
+            assert_eq!(moved, 2, "seed {} : {:?}", seed, nums);
+        }
+    }
+
+    #[test]
+    fn too_few_values_to_swap() {
+        let nums = generate(Distribution::NearlySorted(3), 1, (1, 10), &mut StdRng::seed_from_u64(0));
+        assert_eq!(nums.len(), 1);
+    }
+}
@@ -1,237 +1,1182 @@
 
-use std::net::{TcpListener, TcpStream};
+use std::fs;
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::io::{Read, Write};
-use clap::Parser;
-use std::process::{Command, Stdio};
-use utility::{CommFlags, log, Utility};
-use rand::Rng;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap::error::ErrorKind;
+use std::process::{self, Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use utility::{CommFlags, log, PartialOrder, Utility, OPT_CHECKSUM, OPT_DUPLEX, OPT_EARLY_VOTE, OPT_EARLY_WAVE, OPT_RELIABLE, OPT_TRACE,
+              PROTOCOL_VERSION, TOKEN_ENV, ABORT_MSG, COUNTER_MSG, FINISH_MSG, ORDER_MSG, REPORT_MSG};
+use utility::tls::{NetStream, TlsConfig, TLS_ENV};
+use utility::faults::{ByzantineMode, FaultSpec};
+use algorithms::{registry, AlgoEntry, SortAlgorithm};
+// the algorithms of the crate register themselves once it is linked in
+#[cfg(feature = "extra-algos")]
+use even_odd as _;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use datafile::Format;
+use inputs::Distribution;
+use manifest::{Manifest, Timings, Versions};
+
+// the status lines of `run` go to stderr when the sorted numbers are written to stdout
+static STATUS_TO_STDERR : AtomicBool = AtomicBool::new(false);
+
+// prints a status line of a run, like println
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::STATUS_TO_STDERR.load(std::sync::atomic::Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+mod async_exec;
+mod datafile;
+mod diagram;
+mod experiment;
+mod inputs;
+mod manifest;
+mod metrics;
+mod tls;
+mod verify;
 
 struct Node {
-    port   : u16,
-    stream : TcpStream,
+    port    : u16,
+    stream  : NetStream,
+    failure : Option<Failure>,
 }
 
-#[derive(Parser)]
-#[command(version, 
-    about = "Distributed sorting simulator",
-    long_about = "This program simulates multiple distributed sorting algos using\n\
-                  sockets and seperate processes",
-    author = "Hruthik <hruthikchalamareddy.c22@iiits.in"
-)]
+// How a node ended when it did not Finish
+#[derive(Debug, Clone)]
+enum Failure {
+    // connection dropped without a word, i.e the node crashed
+    Crashed,
+    // gave up on its neighbour on `side` (0 : Left, 1 : Right) in `round` while holding `num`
+    Aborted { reason: AbortReason, side: u8, round: u32, num: i32 },
+}
+
+// Why a node gave up on its neighbour, as numbered in Abort
+#[derive(Debug, Clone, Copy)]
+enum AbortReason {
+    Lost,             // the link broke
+    TimedOut,         // nothing came in time
+    Protocol,         // a message failed its checksum, came from another run or was not the one expected
+    NeverConnected,   // the neighbour did not connect before the rounds
+}
 
+// Faults a node is ordered to act out
+#[derive(Default, Clone, Copy)]
+struct NodeFaults {
+    crash_round : u32,   // crash right before this round, 0 for none
+    byzantine   : u8,    // how it misbehaves (ByzantineMode + 1), 0 for honest
+}
+
+// exit codes of a run that did not sort
+const EXIT_UNSORTED    : i32 = 2;   // every node finished but the output is not the sorted input
+const EXIT_NODE_FAILED : i32 = 3;   // a node crashed or aborted
+const EXIT_REPLAY_DIFF : i32 = 4;   // a replayed run did not reproduce the recorded output
 
+// how long a connection has to report to the distributor before it is dropped
+const REPORT_TIMEOUT : Duration = Duration::from_secs(10);
+
+// What the nodes report about a run along with their final numbers
+#[derive(Default)]
+struct RunStats {
+    rounds_used   : u32,
+    exchange_msgs : u64,   // sent between neighbours by the algorithm
+    control_msgs  : u64,   // sent only to detect termination
+    resent_msgs   : u64,   // sent again by reliable links, not acknowledged in time
+    neighbour_tls : Duration,   // TLS handshakes between neighbours, summed over the nodes
+}
+
+#[derive(Parser)]
 #[command(version, 
     about = "Distributed sorting simulator - Node",
     long_about = "This program simulates multiple distributed sorting algos using\n\
                   sockets and processes, where each process emulates a node.\n\
                   This program emulates distributor.\n",
-    author = "Hruthik <hruthikchalamareddy.c22@iiits.in"
+    author = "Hruthik <hruthikchalamareddy.c22@iiits.in",
+    args_conflicts_with_subcommands = true,
 )]
 struct Args {
 
+    #[command(subcommand)]
+    command : Option<Commands>,
+
+    // without a subcommand the arguments are those of `run`
+    #[command(flatten)]
+    run : RunArgs,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Sort numbers on a line of nodes, the default without a subcommand
+    Run(RunArgs),
+    /// Run every combination of a sweep and write one CSV / JSON row per run
+    #[command(alias = "experiment")]
+    Bench(experiment::ExperimentArgs),
+    /// Rerun the configuration recorded in a manifest and compare the outputs
+    Replay {
+        #[arg(help = "Manifest written by `run --manifest`")]
+        manifest : String,
+    },
+    /// Write the comparator network of an algorithm without running it
+    Visualize(VisualizeArgs),
+    /// Sort every input of small lines and report the ones that come out unsorted
+    Verify(verify::VerifyArgs),
+    /// List the algorithms with the topologies they sort on and their no.of rounds
+    ListAlgos,
+}
+
+#[derive(clap::Args, Clone)]
+struct RunArgs {
+
     #[arg(short, long, 
-        default_value_t = 2,
-        value_parser = clap::value_parser!(u8).range(1..=3),
-        help = "Select your algorithm :     \n\
-                \t 1.Odd Even Transposition \n\
-                \t 2.Sasaki                 \n\
-                \t 3.Triplet (Alternate n-1)",
+        default_value = "sasaki",
+        value_parser = parse_algo,
+        help = "Select your algorithm, see list-algos :\n\
+                \t odd-even       : Odd Even Transposition\n\
+                \t sasaki         : Sasaki\n\
+                \t triplet        : Triplet (Alternate n-1)\n\
+                \t async-odd-even : Async Odd Even (no rounds, runs until no node has work left)",
     )]
-    algo: u8,
+    algo : &'static AlgoEntry,
 
-    #[arg(short, long, 
-        default_value_t = 1,
-        value_parser = clap::value_parser!(u8).range(1..=2),
-        help = "Select partial order :   \n\
-                \t 1. Less than order    \n\
-                \t 2. Greater than order",
+    #[arg(short = 'p', long, 
+        alias = "partial-order",
+        default_value = "asc",
+        value_parser = parse_order,
+        help = "Select partial order : asc (less than) or desc (greater than)",
+    )]
+    order : PartialOrder,
+
+    #[arg(long,
+        default_value = "line",
+        value_parser = parse_topology,
+        help = "Topology of the network, only line for now",
     )]
-    partial_order: u8,
+    topology : String,
 
     #[arg(short, long,
         default_value_t = String::new(),
-        help = "Comma seperated numbers to sort e.g. `--nums 5,3,8,1` \n\
-                (No spaces between numbers).\n\
+        help = "Comma or space seperated numbers to sort e.g. `--nums 5,3,8,1`\n\
                 If nums and test both mentioned, test will be ignored"
     )]
     nums: String,
 
+    #[arg(short, long,
+        help = "Read the numbers to sort from this file, `-` for stdin.\n\
+                Ignored along with --nums, test is ignored along with it",
+    )]
+    input : Option<String>,
+
+    #[arg(long,
+        value_enum,
+        default_value_t = Format::Lines,
+        help = "Format of --input : one number per line, csv / whitespace separated\n\
+                or a binary little-endian i32 array",
+    )]
+    input_format : Format,
+
+    #[arg(short, long,
+        help = "Write the sorted numbers to this file, `-` for stdout.\n\
+                The status lines of the run then go to stderr",
+    )]
+    output : Option<String>,
+
+    #[arg(long,
+        value_enum,
+        help = "Format of --output, defaults to the format of --input",
+    )]
+    output_format : Option<Format>,
+
     #[arg(short, long,
         default_value_t = 500,
         help = "No.of random generated values to be used for testing.\n\
                 Recommended to keep it under 2000, depending on the no.of processes\n\
                 your system can handle",
     )]
-    test : u16
+    test : u32,
+
+    #[arg(long,
+        default_value = "uniform",
+        value_parser = inputs::parse_distribution,
+        help = "Distribution of the values generated for --test :\n\
+                \t uniform, sorted, reverse, nearly-sorted:K (K random swaps, at most one per number),\n\
+                \t equal, few-unique:K, sawtooth:P (period P), organ-pipe, gaussian",
+    )]
+    distribution : Distribution,
+
+    #[arg(long,
+        value_parser = inputs::parse_range,
+        allow_hyphen_values = true,
+        help = "Range of the values generated for --test, `--range -50:50`.\n\
+                Defaults to 1:<no.of values>",
+    )]
+    range : Option<(i32, i32)>,
+
+    #[arg(long,
+        help = "Seed of every random choice of the run : the --test input, the link faults\n\
+                and the values Byzantine nodes make up. Picked and printed when not given",
+    )]
+    seed : Option<u64>,
+
+    #[arg(long,
+        help = "Write a JSON manifest of the run to this file : configuration, seed,\n\
+                input, output, timings and versions",
+    )]
+    manifest : Option<String>,
+
+    #[arg(long,
+        help = "Write the comparator network of the run to this file, with the\n\
+                values held by every node after each round overlaid.\n\
+                Written as html if the extension is .html, svg otherwise",
+    )]
+    diagram : Option<String>,
+
+    #[arg(long,
+        help = "Stop before the fixed no.of rounds once no node changed its value\n\
+                for a full period of the algorithm's pairing.\n\
+                \t vote : nodes vote to the distributor after every round\n\
+                \t wave : nodes detect it among themselves with a wave along the line",
+    )]
+    early_stop : Option<EarlyStop>,
+
+    #[arg(long,
+        value_parser = parse_crash,
+        help = "Crash-stop fault, `--crash 17@40` makes node 17 (1 is the left end)\n\
+                exit right before its round 40. Can be given multiple times",
+    )]
+    crash : Vec<(u32, u32)>,
+
+    #[arg(long,
+        value_parser = parse_byzantine,
+        help = "Byzantine node, `--byzantine 5:lie` makes node 5 misbehave in every exchange :\n\
+                \t lie          : tells both neighbours the same made up value\n\
+                \t inconsistent : tells each neighbour a different made up value\n\
+                \t refuse       : tells the truth but never swaps\n\
+                Defaults to lie. Can be given multiple times",
+    )]
+    byzantine : Vec<(u32, ByzantineMode)>,
+
+    #[arg(long,
+        help = "Faults injected on the links between nodes, e.g.\n\
+                `--faults \"drop=0.01,delay=uniform:0:5,seed=7; edge=3,reorder=0.2\"`\n\
+                Keys : drop=P, delay=fixed:MS|uniform:LO:HI|exp:MEAN, reorder=P, corrupt=P,\n\
+                timeout=MS, retransmit=MS (with --reliable), seed=N,\n\
+                edge=N (only the edge between node N and N+1).\n\
+                Applied after the entries of --fault-config",
+    )]
+    faults : Option<String>,
+
+    #[arg(long,
+        help = "File with fault entries in the --faults format, one per line,\n\
+                `#` starts a comment",
+    )]
+    fault_config : Option<String>,
+
+    #[arg(long,
+        help = "One full-duplex connection per pair of neighbours, made by the left one,\n\
+                instead of one connection each way. Halves the sockets of large runs",
+    )]
+    duplex : bool,
+
+    #[arg(long,
+        help = "TLS on every connection of the run, with a CA made for the run.\n\
+                The time spent on handshakes with the nodes is printed",
+    )]
+    tls : bool,
+
+    #[arg(long,
+        help = "CRC32 on every message between neighbours, a Node receiving a corrupted one\n\
+                (see corrupt= in --faults) aborts instead of sorting with it",
+    )]
+    checksum : bool,
+
+    #[arg(long,
+        help = "Number and acknowledge every message between neighbours, resend the ones not\n\
+                acknowledged in time (retransmit= in --faults, 50ms by default) and discard duplicates.\n\
+                Rounds go through lost messages, along with --checksum corrupted ones too",
+    )]
+    reliable : bool,
+}
+
+#[derive(clap::Args)]
+struct VisualizeArgs {
+
+    #[arg(short, long, 
+        default_value = "sasaki",
+        value_parser = parse_algo,
+        help = "Algorithm to draw, as named by list-algos",
+    )]
+    algo : &'static AlgoEntry,
+
+    #[arg(short, long,
+        default_value_t = 8,
+        help = "No.of nodes of the line",
+    )]
+    nodes : u32,
+
+    #[arg(long,
+        default_value = "line",
+        value_parser = parse_topology,
+        help = "Topology of the network, only line for now",
+    )]
+    topology : String,
+
+    #[arg(short, long,
+        help = "Write the network to this file, as html if the extension is .html, svg otherwise",
+    )]
+    out : String,
+}
+
+// looks the algorithm up in the registry
+fn parse_algo(inp_str: &str) -> Result<&'static AlgoEntry, String> {
+    registry::find(inp_str.trim())
+        .ok_or(format!("Unknown algorithm '{}' ({})", inp_str, registry::names().join(" | ")))
+}
+
+fn parse_order(inp_str: &str) -> Result<PartialOrder, String> {
+    PartialOrder::from_name(inp_str.trim())
+        .ok_or(format!("Unknown order '{}' (asc | desc)", inp_str))
+}
+
+fn parse_topology(inp_str: &str) -> Result<String, String> {
+    match inp_str.trim() {
+        "line" => Ok("line".to_string()),
+        def_val => Err(format!("Unsupported topology '{}' (line)", def_val)),
+    }
+}
+
+// rejects what `algo` cannot run, before any node is spawned
+fn check_algo(algo: &dyn SortAlgorithm, topology: &str, no_nodes: u32) -> Result<(), String> {
+    if !algo.topologies().contains(&topology) {
+        return Err(format!("{} does not sort on a {} ({})", algo.name(), topology, algo.topologies().join(" | ")));
+    }
+    if no_nodes < algo.min_nodes() {
+        return Err(format!("{} needs at least {} nodes, got {}", algo.name(), algo.min_nodes(), no_nodes));
+    }
+    Ok(())
+}
+
+// exits with a usage error, the way clap reports invalid arguments
+fn invalid(msg: &str) -> ! {
+    Args::command().error(ErrorKind::ValueValidation, msg).exit()
+}
+
+// parses a crash fault given as node@round
+fn parse_crash(inp_str: &str) -> Result<(u32, u32), String> {
+    let (node, round) = inp_str.split_once('@')
+        .ok_or(format!("Expected node@round, got '{}'", inp_str))?;
+    let node = node.trim().parse::<u32>().map_err(|e| format!("Invalid node '{}' : {}", node, e))?;
+    let round = round.trim().parse::<u32>().map_err(|e| format!("Invalid round '{}' : {}", round, e))?;
+    if node == 0 || round == 0 {
+        return Err("Nodes and rounds are counted from 1".to_string());
+    }
+    Ok((node, round))
+}
+
+// parses a Byzantine node given as node[:mode]
+fn parse_byzantine(inp_str: &str) -> Result<(u32, ByzantineMode), String> {
+    let (node, mode) = inp_str.split_once(':').unwrap_or((inp_str, "lie"));
+    let node = node.trim().parse::<u32>().map_err(|e| format!("Invalid node '{}' : {}", node, e))?;
+    let mode = ByzantineMode::from_name(mode.trim())
+        .ok_or(format!("Unknown mode '{}' (lie | inconsistent | refuse)", mode))?;
+    if node == 0 {
+        return Err("Nodes are counted from 1".to_string());
+    }
+    Ok((node, mode))
 }
 
-fn parse_nums(inp_str:&str) -> isize{
-    inp_str.trim()
-           .parse::<isize>()
-           .expect(&format!("Failed to parse '{}'", inp_str))
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum EarlyStop {
+    Vote,
+    Wave,
 }
 
-// gets the port number of the server hosted by the connected node
-fn get_node_port (mut stream: TcpStream) -> Node{
-    let mut buffer = [0u8; 15];
-    match stream.read(&mut buffer) {
-        Ok(bytes_read) => {
-            assert_eq!(bytes_read, 3);
-            assert_eq!(buffer[0], CommFlags::Report as u8);
+// reads the Report of a connected node for the port of its server, None for a connection
+// that is not a node of this run or a node built for another layout of the messages
+fn get_node_port (mut stream: NetStream, token: u64) -> Option<Node>{
+    let mut buffer = [0u8; REPORT_MSG];
+    match stream.read_exact(&mut buffer[..4]) {
+        Ok(()) => {
+            if buffer[0] != CommFlags::Report as u8 {
+                eprintln!("Rejected a connection : unexpected message {}", buffer[0]);
+                return None;
+            }
+            if buffer[1] != PROTOCOL_VERSION {
+                eprintln!("Node speaks protocol version {}, the distributor {}. Rebuild both binaries",
+                          buffer[1], PROTOCOL_VERSION);
+                process::exit(1);
+            }
+            if let Err(e) = stream.read_exact(&mut buffer[4..]) {
+                eprintln!("Rejected a connection : no run token ({})", e);
+                return None;
+            }
+            if u64::from_le_bytes(buffer[4..12].try_into().unwrap()) != token {
+                eprintln!("Rejected a connection : wrong run token");
+                return None;
+            }
+            stream.set_read_timeout(None).expect("Failed to set read timeout");
             let port_num = u16::from_le_bytes(
-                           buffer[1..3].try_into()
-                           .expect(&format!("Failed to parse {:?} into u16", &buffer[1..])
+                           buffer[2..4].try_into()
+                           .unwrap_or_else(|_| panic!("Failed to parse {:?} into u16", &buffer[2..])
             ));
-            Node {
+            Some(Node {
                 port : port_num,
                 stream,
-            }
+                failure : None,
+            })
+        },
+        Err(e) => {
+            eprintln!("Rejected a connection : no report ({})", e);
+            None
         },
-        Err(e) =>  panic!("Failed to read :{}", e)
     } 
 }
 
-// generate random numbers for --test
-fn gen_random_nums(count: u16) -> Vec<i32> {
-    let mut rng = rand::rng();
-    (0..count).map(|_| rng.random_range(1..=(count as i32))).collect()
+// generate the numbers for --test
+fn gen_random_nums(count: u32, seed: u64, args: &RunArgs) -> Vec<i32> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let range = args.range.unwrap_or((1, count.min(i32::MAX as u32) as i32));
+    inputs::generate(args.distribution, count, range, &mut rng)
+}
+
+// takes over the configuration recorded in a manifest
+fn apply_manifest(args: &mut RunArgs, manifest: &Manifest) {
+    args.algo = parse_algo(&manifest.algo).unwrap_or_else(|e| panic!("Invalid manifest : {}", e));
+    args.order = parse_order(&manifest.partial_order).unwrap_or_else(|e| panic!("Invalid manifest : {}", e));
+    args.topology = manifest.topology.clone();
+    args.nums = manifest.input.iter().map(|num| num.to_string()).collect::<Vec<String>>().join(",");
+    args.input = None;
+    args.seed = Some(manifest.seed);
+    args.early_stop = manifest.early_stop.as_ref().map(|early_stop| EarlyStop::from_str(early_stop, true)
+        .unwrap_or_else(|e| panic!("Invalid early stop in manifest : {}", e)));
+    args.faults = manifest.faults.clone();
+    args.fault_config = None;
+    args.crash = manifest.crash.iter().map(|crash| parse_crash(crash)
+        .unwrap_or_else(|e| panic!("Invalid crash in manifest : {}", e))).collect();
+    args.byzantine = manifest.byzantine.iter().map(|byzantine| parse_byzantine(byzantine)
+        .unwrap_or_else(|e| panic!("Invalid byzantine node in manifest : {}", e))).collect();
+    args.duplex = manifest.duplex;
+    args.tls = manifest.tls;
+    args.checksum = manifest.checksum;
+    args.reliable = manifest.reliable;
 }
 
 // verifies if the recieved result from the nodes is correct
-fn verify_results(mut input_nums:Vec<i32>, output_nums:Vec<i32>, partial_order : u8) -> bool {
+fn verify_results(input_nums:&[i32], output_nums:&[i32], partial_order : PartialOrder) -> bool {
+    let mut input_nums = input_nums.to_vec();
     match partial_order {
-        1 => input_nums.sort(),
-        2 => input_nums.sort_by(|a, b| b.cmp(a)) ,
-        def_val => panic!("Unexpected partial order given {}", def_val),
+        PartialOrder::LessThan    => input_nums.sort(),
+        PartialOrder::GreaterThan => input_nums.sort_by(|a, b| b.cmp(a)) ,
     };
     input_nums == output_nums
 }
 
-// Invokes all nodes with the distributor's port as an argument
-fn invoke_nodes(distributor_port : u16, no_nodes : u16) {
-    let node_executable = if cfg!(debug_assertions) {
+// combines the fault config file and --faults into one spec, None without any fault
+// the run's seed is used when none is given so the run can be repeated
+fn get_fault_spec(args: &RunArgs, seed: u64) -> Option<FaultSpec> {
+    let mut spec_str = match args.fault_config.as_ref() {
+        Some(path) => fs::read_to_string(path)
+                        .unwrap_or_else(|e| panic!("Failed to read fault config {} : {}", path, e)),
+        None => String::new(),
+    };
+    if let Some(faults) = args.faults.as_ref() {
+        spec_str.push(';');
+        spec_str.push_str(faults);
+    }
+
+    let mut spec = FaultSpec::parse(&spec_str).unwrap_or_else(|e| panic!("Invalid faults : {}", e));
+    if spec.is_empty() {
+        return None;
+    }
+    spec.seed.get_or_insert(seed);
+    Some(spec)
+}
+
+fn node_executable() -> &'static str {
+    if cfg!(debug_assertions) {
         "./target/debug/node"
     } else {
         "./target/release/node"
-    };
+    }
+}
 
-    for i in 0..no_nodes {
-        let args = vec!["--dist-port".to_string(), distributor_port.to_string()];
+// Invokes all nodes with the distributor's port as an argument,
+// the run's seed and the faults they inject on their links
+// the run token and the TLS bundle go in the environment, where other users cannot read them
+fn invoke_nodes(distributor_port : u16, no_nodes : u32, seed : u64, faults : Option<&FaultSpec>,
+                token : u64, tls_bundle : Option<&str>) -> Vec<Child> {
+    (0..no_nodes).map(|i| {
+        let mut args = vec!["--dist-port".to_string(), distributor_port.to_string(),
+                            "--seed".to_string(), seed.to_string()];
+        if let Some(faults) = faults {
+            args.push("--faults".to_string());
+            args.push(faults.to_string());
+        }
         
-        Command::new(node_executable)
+        let mut command = Command::new(node_executable());
+        if let Some(bundle) = tls_bundle {
+            command.env(TLS_ENV, bundle);
+        }
+        command
             .args(&args)
+            .env(TOKEN_ENV, token.to_string())
             // .stdout(Stdio::inherit())
-            // .stderr(Stdio::inherit())
+            // what the nodes reject or give up on shows next to the distributor's own
             .stdout(Stdio::null()) 
-            .stderr(Stdio::null()) 
+            .stderr(Stdio::inherit()) 
             .spawn()
-            .expect(&format!("Failed to start node process {}", i));
-    }
+            .unwrap_or_else(|e| panic!("Failed to start node process {} : {}", i, e))
+    }).collect()
 }
 
-// accepts incoming connections from nodes and stores their port numbers
-fn accept_nodes(listener: TcpListener, node_data : &mut Vec<Node>, max_conn : u16) {
-    for stream in listener.incoming() {
-        match stream {
+// accepts incoming connections from nodes and stores their port numbers,
+// returns the time spent on TLS handshakes
+fn accept_nodes(listener: TcpListener, node_data : &mut Vec<Node>, max_conn : u32, token : u64,
+                tls : Option<&TlsConfig>) -> Duration {
+    let mut handshakes = Duration::ZERO;
+    while (node_data.len() as u32) < max_conn {
+        match Utility::accept(&listener, tls) {
             Ok(stream) => {
-                node_data.push(get_node_port(stream));
-                if node_data.len() as u16 == max_conn {
-                    break;
+                stream.set_read_timeout(Some(REPORT_TIMEOUT)).expect("Failed to set read timeout");
+                if tls.is_some() {
+                    let handshake_started = Instant::now();
+                    if let Err(e) = stream.handshake() {
+                        eprintln!("Rejected a connection : TLS handshake failed ({})", e);
+                        continue;
+                    }
+                    handshakes += handshake_started.elapsed();
                 }
+                node_data.extend(get_node_port(stream, token));
             },
             Err(e) => log!("Incoming Connection failed: {}", e),
         }
     }
+    handshakes
 }
 
 // Prepares the buffer to be sent to each node
-fn prepare_order(buffer: &mut [u8], l_port : u16, r_port : u16, glb_pos : u16, 
-                 num : i32, stream : &mut TcpStream){
-    buffer[5..7].copy_from_slice(&l_port.to_le_bytes());
-    buffer[7..9].copy_from_slice(&r_port.to_le_bytes());
-    buffer[9..11].copy_from_slice(&glb_pos.to_le_bytes());
-    buffer[11..15].copy_from_slice(&num.to_le_bytes());
-    assert_eq!(stream.write(buffer).expect(&format!("Failed to send data")), 15);
+fn prepare_order(buffer: &mut [u8], l_port : u16, r_port : u16, glb_pos : u32, 
+                 num : i32, faults : NodeFaults, stream : &mut NetStream){
+    buffer[7..9].copy_from_slice(&l_port.to_le_bytes());
+    buffer[9..11].copy_from_slice(&r_port.to_le_bytes());
+    buffer[11..15].copy_from_slice(&glb_pos.to_le_bytes());
+    buffer[15..19].copy_from_slice(&num.to_le_bytes());
+    buffer[20..24].copy_from_slice(&faults.crash_round.to_le_bytes());
+    buffer[24] = faults.byzantine;
+    stream.write_all(buffer).expect("Failed to send data");
 }
 
 // sends out the order to each node consisting its num, algo, partialorder 
 // and port numbers of its neighbour nodes
-fn send_order(node_data:&mut Vec<Node>, algo:u8, nums:&Vec<i32>, partial_order : u8) {
-    let buffer = &mut [0u8; 15];
-    buffer[1] = algo - 1;
-    buffer[2] = partial_order - 1;
-    buffer[3..5].copy_from_slice(&(nums.len() as u16).to_le_bytes());
+// the algorithm goes by its registered name, after the fixed ORDER_MSG bytes
+// node_faults[i] are the faults node i+1 has to act out
+// neighbours tell each other the run id when connecting, so a connection left over
+// from another run is not taken for theirs
+fn send_order(node_data:&mut [Node], algo:&str, nums:&[i32], partial_order : PartialOrder, options : u8,
+              node_faults : &[NodeFaults], run_id : u64) {
+    let no_nodes = u32::try_from(nums.len())
+        .unwrap_or_else(|_| panic!("Cannot order {} nodes, at most {} are supported", nums.len(), u32::MAX));
+    assert!(algo.len() <= u8::MAX as usize, "Algorithm name {} is too long", algo);
+    let mut buffer = vec![0u8; ORDER_MSG];
+    buffer.extend_from_slice(algo.as_bytes());
+    let buffer = &mut buffer[..];
+    buffer[0] = CommFlags::Order as u8;
+    buffer[1] = algo.len() as u8;
+    buffer[2] = partial_order as u8;
+    buffer[3..7].copy_from_slice(&no_nodes.to_le_bytes());
+    buffer[19] = options;
+    buffer[25..ORDER_MSG].copy_from_slice(&run_id.to_le_bytes());
+
+    // a single node has no neighbours
+    if node_data.len() == 1 {
+        prepare_order(buffer, 0u16, 0u16, 1u32, nums[0], node_faults[0], &mut node_data[0].stream);
+        return;
+    }
 
-    prepare_order(buffer, 0u16, node_data[1].port, 1u16, 
-             nums[0], &mut node_data[0].stream);
+    prepare_order(buffer, 0u16, node_data[1].port, 1u32, 
+             nums[0], node_faults[0], &mut node_data[0].stream);
 
     for i in 1..node_data.len()-1 {
         prepare_order(buffer, node_data[i-1].port, node_data[i+1].port, 
-            (i+1) as u16, nums[i], &mut node_data[i].stream);
+            (i+1) as u32, nums[i], node_faults[i], &mut node_data[i].stream);
     }
 
     let len = node_data.len();
     prepare_order(buffer, node_data[len-2].port, 0u16, 
-      len as u16, nums[len-1], &mut node_data[len-1].stream);
-}
-
-// recieves the final number from each node
-fn receive_output(node_data:&mut Vec<Node>, output_nums:&mut Vec<i32>){
-    let mut buffer = [0u8; 5];
-    for node in node_data {
-        match node.stream.read(&mut buffer) {
-            Ok(bytes_read) => {
-                assert_eq!(bytes_read, 5); 
-                assert_eq!(buffer[0], CommFlags::Finish as u8);
-                output_nums.push(i32::from_le_bytes(buffer[1..].try_into()
-                                    .expect(&format!("Failed to parse {:?} into i32", &buffer[1..]
-                                ))));
+      no_nodes, nums[len-1], node_faults[len-1], &mut node_data[len-1].stream);
+}
+
+// reads the flag of the next message from a node, None if the node is gone
+fn read_flag(stream: &mut NetStream) -> Option<u8> {
+    let mut flag = [0u8; 1];
+    stream.read_exact(&mut flag).ok().map(|_| flag[0])
+}
+
+// parses an Abort message without its flag
+fn parse_abort(buffer: &[u8]) -> Failure {
+    Failure::Aborted {
+        reason    : match buffer[0] {
+            1 => AbortReason::TimedOut,
+            2 => AbortReason::Protocol,
+            3 => AbortReason::NeverConnected,
+            _ => AbortReason::Lost,
+        },
+        side      : buffer[1],
+        round     : u32::from_le_bytes(buffer[2..6].try_into().unwrap()),
+        num       : i32::from_le_bytes(buffer[6..10].try_into().unwrap()),
+    }
+}
+
+// reads the rest of an Abort message
+fn read_abort(stream: &mut NetStream) -> Failure {
+    let mut buffer = [0u8; ABORT_MSG - 1];
+    match stream.read_exact(&mut buffer) {
+        Ok(()) => parse_abort(&buffer),
+        Err(_) => Failure::Crashed,
+    }
+}
+
+// recieves the values held by a node after each round, sent after Finish
+fn receive_trace(stream: &mut NetStream) -> Vec<i32> {
+    let mut header = [0u8; 5];
+    stream.read_exact(&mut header).expect("Failed to read trace");
+    assert_eq!(header[0], CommFlags::Trace as u8);
+
+    let count = u32::from_le_bytes(header[1..5].try_into().unwrap()) as usize;
+    let mut buffer = vec![0u8; count * 4];
+    stream.read_exact(&mut buffer).expect("Failed to read trace");
+    buffer.chunks_exact(4)
+          .map(|num| i32::from_le_bytes(num.try_into().unwrap()))
+          .collect()
+}
+
+// collects a vote from every node after each round and tells all of them whether to stop
+// the line is done once no node changed its value for `window` consecutive rounds
+fn coordinate_votes(node_data:&mut [Node], rounds : u32, window : u32) {
+    let mut quiet_rounds = 0;
+    let mut buffer = [0u8; 2];
+
+    for _ in 0..rounds {
+        let mut changed = false;
+        for node in node_data.iter_mut().filter(|node| node.failure.is_none()) {
+            match read_flag(&mut node.stream) {
+                Some(flag) if flag == CommFlags::Vote as u8 => {
+                    node.stream.read_exact(&mut buffer[1..]).expect("Failed to read vote");
+                    changed |= buffer[1] == 1;
+                },
+                Some(flag) if flag == CommFlags::Abort as u8 => node.failure = Some(read_abort(&mut node.stream)),
+                Some(def_val) => panic!("Unexpected message from node : {}", def_val),
+                None => node.failure = Some(Failure::Crashed),
+            }
+        }
+
+        // nodes that failed don't vote, the rest carry on without them
+        if node_data.iter().all(|node| node.failure.is_some()) {
+            return;
+        }
+
+        quiet_rounds = if changed { 0 } else { quiet_rounds + 1 };
+        let stop = quiet_rounds >= window;
+
+        buffer = [CommFlags::Verdict as u8, stop as u8];
+        for node in node_data.iter_mut().filter(|node| node.failure.is_none()) {
+            // a node failing right now shows up when its next message is read
+            let _ = node.stream.write(&buffer);
+        }
+
+        if stop {
+            return;
+        }
+    }
+}
+
+// recieves the final number from each node, one slot per node in line order
+// nodes that crashed or aborted are marked with their failure instead and get None
+fn receive_output(node_data:&mut [Node], output_nums:&mut Vec<Option<i32>>, 
+                  mut traces: Option<&mut Vec<Option<Vec<i32>>>>) -> RunStats {
+    let mut buffer = [0u8; FINISH_MSG];
+    let mut stats = RunStats::default();
+    for node in node_data.iter_mut() {
+        let (num, trace) = receive_finish(node, &mut buffer, &mut stats, traces.is_some());
+        output_nums.push(num);
+        if let Some(traces) = traces.as_mut() {
+            traces.push(trace);
+        }
+    }
+    stats
+}
+
+// the number and the trace (when asked for) a node sends along with Finish,
+// None for a node that failed before or instead
+fn receive_finish(node: &mut Node, buffer: &mut [u8; FINISH_MSG], stats: &mut RunStats, 
+                  tracing: bool) -> (Option<i32>, Option<Vec<i32>>) {
+    if node.failure.is_some() {
+        return (None, None);
+    }
+    let mut flag = read_flag(&mut node.stream);
+
+    // counters an async node reported before it saw Stop, when a failure ended the run
+    while flag == Some(CommFlags::Idle as u8) || flag == Some(CommFlags::Status as u8) {
+        node.stream.read_exact(&mut buffer[1..COUNTER_MSG]).expect("Failed to read counters");
+        flag = read_flag(&mut node.stream);
+    }
+
+    match flag {
+        Some(flag) if flag == CommFlags::Finish as u8 => {
+            node.stream.read_exact(&mut buffer[1..]).expect("Failed to read Finish");
+            let num = i32::from_le_bytes(buffer[1..5].try_into()
+                        .unwrap_or_else(|_| panic!("Failed to parse {:?} into i32", &buffer[1..5])));
+            stats.rounds_used = stats.rounds_used.max(u32::from_le_bytes(buffer[5..9].try_into().unwrap()));
+            stats.exchange_msgs += u32::from_le_bytes(buffer[9..13].try_into().unwrap()) as u64;
+            stats.control_msgs += u32::from_le_bytes(buffer[13..17].try_into().unwrap()) as u64;
+            stats.resent_msgs += u32::from_le_bytes(buffer[17..21].try_into().unwrap()) as u64;
+            stats.neighbour_tls += Duration::from_micros(u32::from_le_bytes(buffer[21..25].try_into().unwrap()) as u64);
+            let trace = if tracing { Some(receive_trace(&mut node.stream)) } else { None };
+            return (Some(num), trace);
+        },
+        Some(flag) if flag == CommFlags::Abort as u8 => node.failure = Some(read_abort(&mut node.stream)),
+        Some(def_val) => panic!("Unexpected message from node : {}", def_val),
+        None => node.failure = Some(Failure::Crashed),
+    }
+    (None, None)
+}
+
+// the values of every node in line order, None if a node did not finish
+fn finished_nums(output_nums: &[Option<i32>]) -> Option<Vec<i32>> {
+    output_nums.iter().copied().collect()
+}
+
+// the values of the nodes in line order, `-` for a node that did not finish
+fn format_output(output_nums: &[Option<i32>]) -> String {
+    let nums: Vec<String> = output_nums.iter()
+        .map(|num| num.map_or("-".to_string(), |num| num.to_string()))
+        .collect();
+    format!("[{}]", nums.join(", "))
+}
+
+// reports which nodes failed, how their neighbours reacted and the values lost with them
+fn report_failures(failures: &[Option<Failure>], input_nums: &[i32], output_nums: &[Option<i32>], 
+                   node_faults: &[NodeFaults]) {
+    let mut remaining: Vec<i32> = output_nums.iter().flatten().copied().collect();
+    for (i, failure) in failures.iter().enumerate() {
+        match *failure {
+            Some(Failure::Crashed) if node_faults[i].crash_round != 0 => 
+                status!("=> Node {} crashed before round {} as ordered", i + 1, node_faults[i].crash_round),
+            Some(Failure::Crashed) => status!("=> Node {} crashed", i + 1),
+            Some(Failure::Aborted { reason, side, round, num }) => {
+                let side = if side == 0 { "left" } else { "right" };
+                match reason {
+                    AbortReason::Lost => 
+                        status!("=> Node {} lost its {} neighbour in round {}, holding {}", i + 1, side, round, num),
+                    AbortReason::TimedOut => 
+                        status!("=> Node {} timed out waiting for its {} neighbour in round {}, holding {}",
+                                 i + 1, side, round, num),
+                    AbortReason::Protocol => 
+                        status!("=> Node {} refused a corrupted, foreign or unexpected message from its {} neighbour in round {}, holding {}",
+                                 i + 1, side, round, num),
+                    AbortReason::NeverConnected => 
+                        status!("=> Node {} gave up on its {} neighbour, which never connected, holding {}",
+                                 i + 1, side, num),
+                }
+                remaining.push(num);
             },
-            Err(e) =>  panic!("Failed to read :{}", e)
-        } 
+            None => (),
+        }
+    }
+
+    // whatever is not held by a surviving node was lost
+    let mut lost = input_nums.to_vec();
+    for num in remaining {
+        if let Some(pos) = lost.iter().position(|lost_num| *lost_num == num) {
+            lost.swap_remove(pos);
+        }
+    }
+    lost.sort();
+    status!("Lost values   : {:?}", lost);
+}
+
+// turns per node traces into per round values, starting with the input
+// None unless every node sent its trace
+fn values_per_round(input_nums: &[i32], traces: &[Option<Vec<i32>>]) -> Option<Vec<Vec<i32>>> {
+    let traces: Vec<&Vec<i32>> = traces.iter().map(Option::as_ref).collect::<Option<_>>()?;
+    let rounds = traces.first().map_or(0, |trace| trace.len());
+    let mut values = vec![input_nums.to_vec()];
+    for round in 0..rounds {
+        values.push(traces.iter().map(|trace| trace[round]).collect());
+    }
+    Some(values)
+}
+
+// What a run is asked to do besides sorting its input
+struct RunConfig {
+    algo          : Box<dyn SortAlgorithm>,
+    partial_order : PartialOrder,
+    options       : u8,   // OPT_* bits of the order
+    node_faults   : Vec<NodeFaults>,
+    faults        : Option<FaultSpec>,
+    seed          : u64,
+    tls           : bool,   // nodes are spawned to use TLS
+}
+
+// What came out of a run
+struct RunOutcome {
+    output_nums : Vec<Option<i32>>,        // per node, None for a node that did not finish
+    stats       : RunStats,
+    traces      : Vec<Option<Vec<i32>>>,   // per node, only when OPT_TRACE is set
+    failures    : Vec<Option<Failure>>,    // per node
+    timings     : Timings,
+}
+
+// Node processes connected to the distributor, spawned for a single run
+// or kept as a pool for many (OPT_POOL)
+struct Pool {
+    node_data : Vec<Node>,
+    children  : Vec<Child>,
+    tls_ms    : f64,   // TLS handshakes with the nodes, counted in the first run
+}
+
+impl Pool {
+    // spawns `no_nodes` nodes and waits for all of them to connect
+    // over TLS when `tls`, with a CA made for the pool
+    fn spawn(no_nodes : u32, seed : u64, faults : Option<&FaultSpec>, tls : bool, verbose : bool) -> Pool {
+        let progress = |msg: &str| if verbose { status!("=> {}", msg) };
+        let mut node_data:Vec<Node> = Vec::new();
+        let (listener, port) = Utility::create_server();
+        progress(&format!("Distributor server running on port : {}", port));
+
+        // every pool has its own token, nodes of another pool or run cannot connect
+        let token = rand::random();
+        let tls_bundle = tls.then(tls::generate_bundle);
+        let tls_config = tls_bundle.as_ref().map(|bundle| TlsConfig::from_pem(bundle)
+            .unwrap_or_else(|e| panic!("Invalid TLS bundle : {}", e)));
+        let children = invoke_nodes(port, no_nodes, seed, faults, token, tls_bundle.as_deref());
+        progress("Nodes invoked");
+
+        let handshakes = accept_nodes(listener, &mut node_data, no_nodes, token, tls_config.as_ref());
+        progress("Nodes connected");
+        Pool { node_data, children, tls_ms: handshakes.as_secs_f64() * 1000.0 }
     }
+
+    // hangs up on every node, nodes waiting for the next order exit
+    fn close(self) {
+        drop(self.node_data);
+        for mut child in self.children {
+            child.wait().expect("Failed to wait on node process");
+        }
+    }
+}
+
+// Spawns a node per input value, has them sort the input and collects the outputs
+// the steps are printed when `verbose`
+fn run_nodes(config: &RunConfig, input_nums: &[i32], verbose: bool) -> RunOutcome {
+    let started = Instant::now();
+    let mut pool = Pool::spawn(input_nums.len() as u32, config.seed, config.faults.as_ref(), config.tls, verbose);
+    let outcome = run_on_pool(&mut pool, config, input_nums, verbose, started);
+    pool.close();
+    outcome
+}
+
+// Runs one sort on the first nodes of the pool, the rest are told to drop their links.
+// Set OPT_POOL in the config for the nodes to wait for the next run.
+fn run_on_pool(pool: &mut Pool, config: &RunConfig, input_nums: &[i32], verbose: bool, 
+               started: Instant) -> RunOutcome {
+    let algo = config.algo.as_ref();
+    let no_nodes = input_nums.len() as u32;
+    assert!(input_nums.len() <= pool.node_data.len(), "Pool of {} nodes cannot sort {} numbers", 
+            pool.node_data.len(), input_nums.len());
+
+    let (node_data, idle) = pool.node_data.split_at_mut(input_nums.len());
+    for node in idle {
+        node.stream.write_all(&[CommFlags::Reset as u8]).expect("Failed to send reset");
+    }
+    for node in node_data.iter_mut() {
+        node.failure = None;
+    }
+
+    let mut output_nums: Vec<Option<i32>> = Vec::new();
+    let run_id = rand::random();
+    send_order(node_data, algo.name(), input_nums, config.partial_order, config.options, &config.node_faults, run_id);
+    if verbose {
+        status!("=> Order sent to the nodes");
+    }
+    let sort_started = Instant::now();
+
+    // a single node finishes right away, there is nothing to coordinate
+    if no_nodes > 1 && algo.is_async() {
+        async_exec::coordinate_async(node_data);
+    }
+    else if no_nodes > 1 && config.options & OPT_EARLY_VOTE != 0 {
+        coordinate_votes(node_data, algo.rounds(no_nodes), algo.quiet_window());
+    }
+
+    let mut traces = Vec::new();
+    let tracing = config.options & OPT_TRACE != 0;
+    let stats = receive_output(node_data, &mut output_nums, if tracing { Some(&mut traces) } else { None });
+    let timings = Timings {
+        setup_ms : (sort_started - started).as_secs_f64() * 1000.0,
+        sort_ms  : sort_started.elapsed().as_secs_f64() * 1000.0,
+        total_ms : started.elapsed().as_secs_f64() * 1000.0,
+        tls_ms   : std::mem::take(&mut pool.tls_ms),
+        neighbour_tls_ms : stats.neighbour_tls.as_secs_f64() * 1000.0,
+    };
+
+    let failures = node_data.iter().map(|node| node.failure.clone()).collect();
+    RunOutcome { output_nums, stats, traces, failures, timings }
+}
+
+// prints every algorithm with what it needs to run
+fn list_algos() {
+    println!("{:<16} {:<12} {:<10} DESCRIPTION", "NAME", "TOPOLOGIES", "MIN NODES");
+    for entry in registry::entries() {
+        let algo = entry.construct();
+        println!("{:<16} {:<12} {:<10} {}", algo.name(), algo.topologies().join(","), algo.min_nodes(), 
+                 algo.description());
+    }
+}
+
+fn visualize(args: &VisualizeArgs) {
+    let algo = args.algo.construct();
+    check_algo(algo.as_ref(), &args.topology, args.nodes).unwrap_or_else(|e| invalid(&e));
+    if algo.is_async() {
+        invalid(&format!("{} has no rounds, there is no fixed network to draw", algo.name()));
+    }
+    diagram::write_diagram(&args.out, algo.as_ref(), args.nodes, None);
+    println!("=> Diagram written to {}", args.out);
+}
+
+fn replay(path: &str) {
+    let manifest = Manifest::load(path);
+    let mut args = Args::parse_from(["distributor"]).run;
+    apply_manifest(&mut args, &manifest);
+    println!("=> Replaying {}", path);
+    run(args, Some(&manifest));
 }
 
 fn main() {
     let args = Args::parse();
+    match args.command {
+        Some(Commands::Run(run_args)) => run(run_args, None),
+        Some(Commands::Bench(bench_args)) => experiment::run_experiment(&bench_args),
+        Some(Commands::Replay { manifest }) => replay(&manifest),
+        Some(Commands::Visualize(visualize_args)) => visualize(&visualize_args),
+        Some(Commands::Verify(verify_args)) => verify::run_verify(&verify_args),
+        Some(Commands::ListAlgos) => list_algos(),
+        None => run(args.run, None),
+    }
+}
+
+// sorts the input on a line of nodes, compares the output against `replay` when given
+fn run(args: RunArgs, replay: Option<&Manifest>) {
+    // `--output -` keeps stdout for the sorted numbers, so they can be piped
+    STATUS_TO_STDERR.store(args.output.as_deref() == Some(datafile::STD_STREAM), Ordering::Relaxed);
     let input_nums:Vec<i32>;
-    let no_nodes:u16;
+    let algorithm = args.algo.construct();
+    let algo = algorithm.as_ref();
+    let generated = args.nums.is_empty() && args.input.is_none();
 
-    if args.nums.len() == 0 {
-        no_nodes = args.test;
-        input_nums = gen_random_nums(no_nodes);
-        println!("Input nums :\n{:?}", input_nums);
+    // the size of a --test input is known up front, others are checked once read
+    check_algo(algo, &args.topology, if generated { args.test } else { algo.min_nodes() })
+        .unwrap_or_else(|e| invalid(&e));
+    if args.early_stop.is_some() && algo.is_async() {
+        invalid(&format!("--early-stop needs a round based algorithm, {} stops by itself", algo.name()));
     }
 
+    let seed = args.seed.unwrap_or_else(rand::random);
+    status!("Seed          : {}", seed);
+    let distribution = if generated { Some(args.distribution.to_string()) } else { None };
+
+    if !args.nums.is_empty() {
+        input_nums = datafile::parse_text(&args.nums).unwrap_or_else(|e| panic!("Failed to parse --nums : {}", e));
+    }
+    else if let Some(path) = args.input.as_ref() {
+        input_nums = datafile::read_nums(path, args.input_format);
+    }
     else {
-        input_nums = args.nums
-            .split(',')
-            .map(|s| parse_nums(s) as i32)
-            .collect();
-        no_nodes = input_nums.len() as u16;
+        input_nums = gen_random_nums(args.test, seed, &args);
+        status!("Input nums :\n{:?}", input_nums);
+    }
+
+    let no_nodes = u32::try_from(input_nums.len()).unwrap_or_else(|_| 
+        invalid(&format!("Cannot sort {} numbers, at most {} nodes are supported", input_nums.len(), u32::MAX)));
+    check_algo(algo, &args.topology, no_nodes).unwrap_or_else(|e| invalid(&e));
+    let rounds = algo.rounds(no_nodes);
+
+    let mut node_faults = vec![NodeFaults::default(); no_nodes as usize];
+    for &(node, round) in &args.crash {
+        if algo.is_async() {
+            invalid("Crash faults need a round based algorithm");
+        }
+        if node > no_nodes {
+            invalid(&format!("Cannot crash node {}, there are only {} nodes", node, no_nodes));
+        }
+        if round > rounds {
+            invalid(&format!("Cannot crash in round {}, {} runs {} rounds", round, algo.name(), rounds));
+        }
+        node_faults[node as usize - 1].crash_round = round;
+    }
+
+    for &(node, mode) in &args.byzantine {
+        if algo.is_async() {
+            invalid("Byzantine nodes need a round based algorithm");
+        }
+        if node > no_nodes {
+            invalid(&format!("Node {} cannot be byzantine, there are only {} nodes", node, no_nodes));
+        }
+        node_faults[node as usize - 1].byzantine = mode as u8 + 1;
+    }
+
+    let faults = get_fault_spec(&args, seed);
+    if let Some(faults) = faults.as_ref() {
+        status!("Faults        : {}", faults);
     }
 
+    status!("Algo          : {}\n\
+              Partial order : {}", algo.name(), args.order.name());
 
-    let mut output_nums: Vec<i32>  = Vec::new();
-    let mut node_data:Vec<Node> = Vec::new();
-    let (listener, port) = Utility::create_server();
+    let mut options = if args.diagram.is_some() { OPT_TRACE } else { 0 };
+    if args.duplex {
+        options |= OPT_DUPLEX;
+    }
+    if args.checksum {
+        options |= OPT_CHECKSUM;
+    }
+    if args.reliable {
+        options |= OPT_RELIABLE;
+    }
+    match args.early_stop {
+        Some(EarlyStop::Vote) => options |= OPT_EARLY_VOTE,
+        Some(EarlyStop::Wave) => options |= OPT_EARLY_WAVE,
+        None => (),
+    }
+
+    let config = RunConfig {
+        algo          : args.algo.construct(),
+        partial_order : args.order,
+        options,
+        node_faults,
+        faults,
+        seed,
+        tls           : args.tls,
+    };
+    let RunOutcome { output_nums, stats, traces, failures, timings } = run_nodes(&config, &input_nums, true);
+
+    // `-` marks a node that did not finish, see the failures below
+    status!("Output :\n{}", format_output(&output_nums));
+    let finished = finished_nums(&output_nums);
+    if let Some(path) = args.output.as_ref() {
+        match finished.as_ref() {
+            Some(nums) => datafile::write_nums(path, nums, args.output_format.unwrap_or(args.input_format)),
+            None => status!("=> Output file skipped, not every node finished"),
+        }
+    }
+    if !algo.is_async() {
+        status!("Rounds used   : {} of {}", stats.rounds_used, rounds);
+    }
+    status!("Messages      : {} exchange, {} termination", stats.exchange_msgs, stats.control_msgs);
+    if args.reliable {
+        status!("Resent        : {} messages not acknowledged in time", stats.resent_msgs);
+    }
+    if args.tls {
+        status!("TLS           : {:.3} ms of handshakes with the nodes, {:.3} ms between neighbours \
+                 (summed over the nodes), {:.3} ms sorting", timings.tls_ms, timings.neighbour_tls_ms, timings.sort_ms);
+    }
+
+    let failed = failures.iter().any(|failure| failure.is_some());
+    if failed {
+        report_failures(&failures, &input_nums, &output_nums, &config.node_faults);
+    }
+
+    // how far from sorted the output ended up
+    let sorted = finished.as_ref().is_some_and(|nums| verify_results(&input_nums, nums, args.order));
+    if sorted {
+        status!("Verification  : passed");
+    }
+    else {
+        status!("Verification  : failed");
+        // the values held by failed nodes are missing from the output, see Lost values
+        match finished.as_ref() {
+            Some(nums) => metrics::report(&input_nums, nums, args.order),
+            None => status!("=> Metrics skipped, not every node finished"),
+        }
+    }
 
-    println!("Algo          : {:?}\n\
-              Partial order : {:?}", args.algo, args.partial_order);
+    if let Some(path) = args.diagram.as_ref() {
+        match values_per_round(&input_nums, &traces) {
+            Some(values) => {
+                diagram::write_diagram(path, algo, no_nodes, Some(&values));
+                status!("=> Diagram written to {}", path);
+            },
+            None => status!("=> Diagram skipped, not every node finished"),
+        }
+    }
 
-    println!("=> Distributor server running on port : {}", port);
-    
-    invoke_nodes(port, no_nodes);
-    println!("=> Nodes invoked");
+    if let Some(path) = args.manifest.as_ref() {
+        let manifest = Manifest {
+            algo          : algo.name().to_string(),
+            partial_order : args.order.name().to_string(),
+            topology      : args.topology.clone(),
+            seed,
+            distribution,
+            early_stop    : args.early_stop.and_then(|early_stop| early_stop.to_possible_value())
+                                .map(|value| value.get_name().to_string()),
+            faults        : config.faults.as_ref().map(|faults| faults.to_string()),
+            crash         : args.crash.iter().map(|(node, round)| format!("{}@{}", node, round)).collect(),
+            byzantine     : args.byzantine.iter().map(|(node, mode)| format!("{}:{}", node, mode.name())).collect(),
+            duplex        : args.duplex,
+            tls           : args.tls,
+            checksum      : args.checksum,
+            reliable      : args.reliable,
+            input         : input_nums.clone(),
+            output        : output_nums.clone(),
+            verified      : sorted,
+            timings,
+            versions      : Versions::current(node_executable()),
+        };
+        manifest.save(path);
+        status!("=> Manifest written to {}", path);
+    }
 
-    accept_nodes(listener, &mut node_data, no_nodes);
-    println!("=> Nodes connected");
+    if let Some(manifest) = replay {
+        if manifest.output == output_nums {
+            status!("Replay        : output matches the recorded run");
+        }
+        else {
+            status!("Replay        : output differs from the recorded run\n{}", format_output(&manifest.output));
+            process::exit(EXIT_REPLAY_DIFF);
+        }
+    }
 
-    send_order(&mut node_data, args.algo, &input_nums, args.partial_order);
-    println!("=> Order sent to the nodes");
+    if failed {
+        process::exit(EXIT_NODE_FAILED);
+    }
+    if !sorted {
+        process::exit(EXIT_UNSORTED);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    receive_output(&mut node_data, &mut output_nums);
-    println!("Output :\n{:?}", output_nums);
+    #[test]
+    fn failed_nodes_keep_their_slot() {
+        let output_nums = [Some(1), None, Some(3)];
+        assert_eq!(format_output(&output_nums), "[1, -, 3]");
+        assert_eq!(finished_nums(&output_nums), None);
+        assert_eq!(finished_nums(&[Some(1), Some(3)]), Some(vec![1, 3]));
+    }
 
-    assert!(verify_results(input_nums, output_nums, args.partial_order));
-}
\ No newline at end of file
+    #[test]
+    fn values_per_round_need_every_trace() {
+        let traces = [Some(vec![2, 1]), Some(vec![1, 2])];
+        assert_eq!(values_per_round(&[2, 1], &traces), Some(vec![vec![2, 1], vec![2, 1], vec![1, 2]]));
+        assert_eq!(values_per_round(&[2, 1], &[Some(vec![2, 1]), None]), None);
+    }
+}
@@ -0,0 +1,95 @@
+use std::fs;
+use std::process::Command;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use utility::PartialOrder;
+
+// Everything needed to rerun a run exactly, along with what it produced.
+// Written with `run --manifest`, read back by `replay`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Manifest {
+    pub algo          : String,   // as registered, given to --algo
+    #[serde(deserialize_with = "order_name")]
+    pub partial_order : String,   // as given to --order
+    pub topology      : String,
+    pub seed          : u64,
+    pub distribution  : Option<String>,   // None when the input was given with --nums
+    pub early_stop    : Option<String>,
+    pub faults        : Option<String>,
+    pub crash         : Vec<String>,      // node@round
+    pub byzantine     : Vec<String>,      // node:mode
+    #[serde(default)]
+    pub duplex        : bool,             // one connection per neighbour pair, missing before it existed
+    #[serde(default)]
+    pub tls           : bool,
+    #[serde(default)]
+    pub checksum      : bool,
+    #[serde(default)]
+    pub reliable      : bool,
+    pub input         : Vec<i32>,
+    pub output        : Vec<Option<i32>>,   // per node, null for a node that did not finish
+    pub verified      : bool,
+    pub timings       : Timings,
+    pub versions      : Versions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Timings {
+    pub setup_ms : f64,   // spawning the nodes until the order is sent
+    pub sort_ms  : f64,   // order sent until every output is in
+    pub total_ms : f64,
+    #[serde(default)]
+    pub tls_ms   : f64,   // TLS handshakes of the distributor with the nodes as they connected
+    #[serde(default)]
+    pub neighbour_tls_ms : f64,   // TLS handshakes between neighbours, summed over the nodes
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Versions {
+    pub distributor : String,
+    pub node        : String,
+}
+
+impl Versions {
+    // the node binary reports its own version, it may have been built apart
+    pub fn current(node_executable: &str) -> Versions {
+        let node = Command::new(node_executable).arg("--version").output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .unwrap_or_else(|e| format!("unknown ({})", e));
+        Versions {
+            distributor : format!("distributor {}", env!("CARGO_PKG_VERSION")),
+            node,
+        }
+    }
+}
+
+// Manifests written before orders were named hold 1 for asc and 2 for desc
+fn order_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Order {
+        Name(String),
+        Number(u8),
+    }
+    match Order::deserialize(deserializer)? {
+        Order::Name(name) => Ok(name),
+        Order::Number(1) => Ok(PartialOrder::LessThan.name().to_string()),
+        Order::Number(2) => Ok(PartialOrder::GreaterThan.name().to_string()),
+        Order::Number(number) => Err(serde::de::Error::custom(format!("unknown partial order {}", number))),
+    }
+}
+
+impl Manifest {
+    pub fn load(path: &str) -> Manifest {
+        let content = fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("Failed to read manifest {} : {}", path, e));
+        serde_json::from_str(&content)
+            .unwrap_or_else(|e| panic!("Invalid manifest {} : {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) {
+        let content = serde_json::to_string_pretty(self).expect("Failed to serialise manifest");
+        fs::write(path, content + "\n")
+            .unwrap_or_else(|e| panic!("Failed to write manifest to {} : {}", path, e));
+    }
+}
@@ -0,0 +1,167 @@
+use utility::PartialOrder;
+
+// How far an output is from sorted with respect to `partial_order`
+
+fn out_of_order(partial_order: PartialOrder, first: i32, second: i32) -> bool {
+    match partial_order {
+        PartialOrder::LessThan    => first > second,
+        PartialOrder::GreaterThan => first < second,
+    }
+}
+
+// No.of pairs (i, j), i < j, where nums[i] has to come after nums[j]
+// counted while merge sorting, O(n log n)
+pub fn inversions(nums: &[i32], partial_order: PartialOrder) -> u64 {
+    fn sort_count(nums: &mut [i32], partial_order: PartialOrder) -> u64 {
+        if nums.len() < 2 {
+            return 0;
+        }
+        let mid = nums.len() / 2;
+        let mut count = sort_count(&mut nums[..mid], partial_order) +
+                        sort_count(&mut nums[mid..], partial_order);
+
+        let (left, right) = (nums[..mid].to_vec(), nums[mid..].to_vec());
+        let (mut i, mut j) = (0, 0);
+        for slot in nums.iter_mut() {
+            if j == right.len() || (i < left.len() && !out_of_order(partial_order, left[i], right[j])) {
+                *slot = left[i];
+                i += 1;
+            }
+            else {
+                // every value left in `left` comes after right[j]
+                count += (left.len() - i) as u64;
+                *slot = right[j];
+                j += 1;
+            }
+        }
+        count
+    }
+    sort_count(&mut nums.to_vec(), partial_order)
+}
+
+// Distance of every value from the position it would hold once sorted,
+// equal values keep their relative order. Returns (max, total)
+pub fn displacement(nums: &[i32], partial_order: PartialOrder) -> (usize, u64) {
+    let mut positions: Vec<usize> = (0..nums.len()).collect();
+    positions.sort_by(|&a, &b| match partial_order {
+        PartialOrder::LessThan    => nums[a].cmp(&nums[b]),
+        PartialOrder::GreaterThan => nums[b].cmp(&nums[a]),
+    });
+
+    positions.iter().enumerate()
+        .map(|(sorted_pos, &pos)| sorted_pos.abs_diff(pos))
+        .fold((0, 0), |(max, total), dist| (max.max(dist), total + dist as u64))
+}
+
+// Length of the longest stretch of consecutive values already in order
+pub fn longest_sorted_run(nums: &[i32], partial_order: PartialOrder) -> usize {
+    if nums.is_empty() {
+        return 0;
+    }
+    let (mut longest, mut current) = (1, 1);
+    for pair in nums.windows(2) {
+        current = if out_of_order(partial_order, pair[0], pair[1]) { 1 } else { current + 1 };
+        longest = longest.max(current);
+    }
+    longest
+}
+
+// Multiset difference between input and output : (missing from the output, extra in it)
+// extra values are duplicates, or made up ones when a node lied
+pub fn multiset_diff(input_nums: &[i32], output_nums: &[i32]) -> (Vec<i32>, Vec<i32>) {
+    let (mut input, mut output) = (input_nums.to_vec(), output_nums.to_vec());
+    input.sort();
+    output.sort();
+
+    let (mut missing, mut extra) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    while i < input.len() || j < output.len() {
+        if j == output.len() || (i < input.len() && input[i] < output[j]) {
+            missing.push(input[i]);
+            i += 1;
+        }
+        else if i == input.len() || output[j] < input[i] {
+            extra.push(output[j]);
+            j += 1;
+        }
+        else {
+            i += 1;
+            j += 1;
+        }
+    }
+    (missing, extra)
+}
+
+// Prints how far the output is from the sorted input
+pub fn report(input_nums: &[i32], output_nums: &[i32], partial_order: PartialOrder) {
+    let (max_dist, total_dist) = displacement(output_nums, partial_order);
+    let (missing, extra) = multiset_diff(input_nums, output_nums);
+    status!("Inversions    : {}", inversions(output_nums, partial_order));
+    status!("Longest run   : {} of {} sorted in a row", longest_sorted_run(output_nums, partial_order), 
+             output_nums.len());
+    status!("Displacement  : max {}, total {}", max_dist, total_dist);
+    status!("Missing       : {:?}", missing);
+    status!("Extra         : {:?}", extra);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use PartialOrder::{GreaterThan, LessThan};
+
+    // every pair checked one by one
+    fn inversions_by_pairs(nums: &[i32], partial_order: PartialOrder) -> u64 {
+        let mut count = 0;
+        for i in 0..nums.len() {
+            for j in i + 1..nums.len() {
+                if out_of_order(partial_order, nums[i], nums[j]) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn inversions_match_the_pairs_out_of_order() {
+        assert_eq!(inversions(&[], LessThan), 0);
+        assert_eq!(inversions(&[1, 2, 3], LessThan), 0);
+        assert_eq!(inversions(&[3, 2, 1], LessThan), 3);
+        assert_eq!(inversions(&[3, 2, 1], GreaterThan), 0);
+        assert_eq!(inversions(&[2, 2, 1, 1], LessThan), 4);
+
+        let nums = [5, -1, 3, 3, 8, 0, 2, 7, 7, 1, -4];
+        for partial_order in [LessThan, GreaterThan] {
+            assert_eq!(inversions(&nums, partial_order), inversions_by_pairs(&nums, partial_order));
+        }
+    }
+
+    #[test]
+    fn displacement_of_values_from_their_sorted_position() {
+        assert_eq!(displacement(&[], LessThan), (0, 0));
+        assert_eq!(displacement(&[1, 2, 3], LessThan), (0, 0));
+        assert_eq!(displacement(&[3, 2, 1], LessThan), (2, 4));
+        assert_eq!(displacement(&[3, 2, 1], GreaterThan), (0, 0));
+        assert_eq!(displacement(&[2, 1, 3, 4], LessThan), (1, 2));
+        // equal values keep their order, none of them moves
+        assert_eq!(displacement(&[1, 1, 1], LessThan), (0, 0));
+    }
+
+    #[test]
+    fn longest_run_in_order() {
+        assert_eq!(longest_sorted_run(&[], LessThan), 0);
+        assert_eq!(longest_sorted_run(&[4], LessThan), 1);
+        assert_eq!(longest_sorted_run(&[1, 2, 2, 0, 5], LessThan), 3);
+        assert_eq!(longest_sorted_run(&[1, 2, 2, 0, 5], GreaterThan), 3);
+    }
+
+    #[test]
+    fn multiset_diff_of_input_and_output() {
+        assert_eq!(multiset_diff(&[3, 1, 2], &[1, 2, 3]), (vec![], vec![]));
+        assert_eq!(multiset_diff(&[3, 1, 2], &[1, 2]), (vec![3], vec![]));
+        assert_eq!(multiset_diff(&[2, 2, 1], &[1, 2, 9]), (vec![2], vec![9]));
+        assert_eq!(multiset_diff(&[1, 1], &[1, 1, 1]), (vec![], vec![1]));
+        assert_eq!(multiset_diff(&[5, 4], &[]), (vec![4, 5], vec![]));
+    }
+}
@@ -0,0 +1,22 @@
+use rcgen::{BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair, KeyUsagePurpose};
+
+// Makes the TLS bundle of a run : a CA that only lives for the run and a certificate
+// for 127.0.0.1 signed by it, used by the distributor and every node as both
+// client and server. Given to TlsConfig::from_pem and to the nodes in TLS_ENV
+// (all of them on this host, nodes elsewhere would need their address in the certificate)
+pub fn generate_bundle() -> String {
+    let ca_key = KeyPair::generate().expect("Failed to generate the CA key");
+    let mut ca_params = CertificateParams::new(Vec::<String>::new()).expect("Invalid CA parameters");
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    ca_params.distinguished_name.push(DnType::CommonName, "distributed sorting run CA");
+    ca_params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::DigitalSignature];
+    let ca_cert = ca_params.self_signed(&ca_key).expect("Failed to sign the CA certificate");
+
+    let key = KeyPair::generate().expect("Failed to generate the key");
+    let mut params = CertificateParams::new(vec!["127.0.0.1".to_string()]).expect("Invalid certificate parameters");
+    params.distinguished_name.push(DnType::CommonName, "distributed sorting run");
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth, ExtendedKeyUsagePurpose::ClientAuth];
+    let cert = params.signed_by(&key, &ca_cert, &ca_key).expect("Failed to sign the certificate");
+
+    format!("{}{}{}", ca_cert.pem(), cert.pem(), key.serialize_pem())
+}
@@ -0,0 +1,209 @@
+use std::process;
+use std::time::Instant;
+
+use algorithms::{registry, AlgoEntry};
+use utility::{PartialOrder, OPT_POOL};
+
+use crate::{check_algo, finished_nums, format_output, invalid, parse_algo, parse_order, run_on_pool, verify_results,
+            NodeFaults, Pool, RunConfig, RunOutcome, EXIT_UNSORTED};
+
+// Sorts every input of small lines and reports the ones that come out unsorted.
+// Inputs of n nodes are all n^n sequences of values in 1..=n, so every permutation
+// and every pattern of ties is covered
+
+#[derive(clap::Args, Debug)]
+pub struct VerifyArgs {
+    #[arg(long,
+        default_value_t = 4,
+        value_parser = clap::value_parser!(u32).range(1..=6),
+        help = "Lines of 1 up to this many nodes are verified, n^n inputs each",
+    )]
+    max_nodes : u32,
+
+    #[arg(long,
+        value_delimiter = ',',
+        value_parser = parse_algo,
+        help = "Algorithms to verify, as named by list-algos. All of them when not given",
+    )]
+    algos : Vec<&'static AlgoEntry>,
+
+    #[arg(long,
+        default_value = "asc,desc",
+        value_delimiter = ',',
+        value_parser = parse_order,
+        help = "Partial orders to verify, asc and / or desc",
+    )]
+    orders : Vec<PartialOrder>,
+}
+
+// every sequence of `no_nodes` values in 1..=no_nodes, counting up like an odometer
+fn all_inputs(no_nodes: u32) -> Vec<Vec<i32>> {
+    let base = no_nodes as i32;
+    let mut inputs = Vec::new();
+    let mut nums = vec![1; no_nodes as usize];
+    loop {
+        inputs.push(nums.clone());
+        match nums.iter().rposition(|num| *num < base) {
+            Some(pos) => {
+                nums[pos] += 1;
+                nums[pos + 1..].fill(1);
+            },
+            None => return inputs,
+        }
+    }
+}
+
+pub fn run_verify(args: &VerifyArgs) {
+    let algos = if args.algos.is_empty() { registry::entries().to_vec() } else { args.algos.clone() };
+    for algo in &algos {
+        check_algo(algo.construct().as_ref(), "line", 1).unwrap_or_else(|e| invalid(&e));
+    }
+
+    let (mut runs, mut failed) = (0, 0);
+    let mut pool: Option<Pool> = None;
+
+    for algo in algos {
+        for &partial_order in &args.orders {
+            for no_nodes in 1..=args.max_nodes {
+                let inputs = all_inputs(no_nodes);
+                let mut algo_failed = 0;
+
+                for input_nums in &inputs {
+                    let config = RunConfig {
+                        algo        : algo.construct(),
+                        partial_order,
+                        options     : OPT_POOL,
+                        node_faults : vec![NodeFaults::default(); no_nodes as usize],
+                        faults      : None,
+                        seed        : 0,
+                        tls         : false,
+                    };
+                    let nodes = pool.get_or_insert_with(|| Pool::spawn(args.max_nodes, 0, None, false, false));
+                    let RunOutcome { output_nums, failures, .. } =
+                        run_on_pool(nodes, &config, input_nums, false, Instant::now());
+                    runs += 1;
+
+                    let crashed = failures.iter().any(|failure| failure.is_some());
+                    let sorted = finished_nums(&output_nums)
+                        .is_some_and(|nums| verify_results(input_nums, &nums, partial_order));
+                    if !sorted {
+                        println!("FAILED {} {} : {:?} -> {}", algo.name(), partial_order.name(),
+                                 input_nums, format_output(&output_nums));
+                        algo_failed += 1;
+                    }
+                    // nodes of a failed run may be out of step, start afresh
+                    if crashed {
+                        if let Some(pool) = pool.take() {
+                            pool.close();
+                        }
+                    }
+                }
+
+                eprintln!("{} {} n={} : {} inputs, {}", algo.name(), partial_order.name(), no_nodes,
+                          inputs.len(), if algo_failed == 0 { "passed".to_string() }
+                                        else { format!("{} FAILED", algo_failed) });
+                failed += algo_failed;
+            }
+        }
+    }
+
+    if let Some(pool) = pool {
+        pool.close();
+    }
+    println!("Verified      : {} inputs, {} failed", runs, failed);
+    if failed != 0 {
+        process::exit(EXIT_UNSORTED);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpStream;
+    use std::thread;
+
+    use algorithms::termination::Termination;
+    use algorithms::{Link, MemChannel, NodeContext, RelativePos};
+    use utility::tls::NetStream;
+    use utility::Utility;
+
+    use super::*;
+    use crate::{async_exec, Node};
+
+    // Sorts on a line of threads, one per value, linked over MemChannel. The nodes of
+    // an async algorithm report to the distributor's coordinator over loopback TCP
+    fn sort_in_process(entry: &'static AlgoEntry, input_nums: &[i32], partial_order: PartialOrder) -> Vec<i32> {
+        let no_nodes = input_nums.len() as u32;
+        // a single node has no links and holds its value as is, like the node binary
+        if no_nodes == 1 {
+            return input_nums.to_vec();
+        }
+        let is_async = entry.construct().is_async();
+        let (listener, port) = Utility::create_server();
+
+        let mut left_links = vec![None];
+        let mut right_links = Vec::new();
+        for _ in 1..no_nodes {
+            let (right, left) = MemChannel::pair();
+            right_links.push(Some(Link::new(Box::new(right), RelativePos::Right)));
+            left_links.push(Some(Link::new(Box::new(left), RelativePos::Left)));
+        }
+        right_links.push(None);
+
+        thread::scope(|scope| {
+            let nodes: Vec<_> = left_links.into_iter().zip(right_links).zip(input_nums).enumerate()
+                .map(|(i, ((left_link, right_link), &num))| {
+                    let distributor = is_async.then(|| TcpStream::connect(("127.0.0.1", port))
+                        .expect("Failed to connect to the coordinator"));
+                    scope.spawn(move || {
+                        let algo = entry.construct();
+                        let mut ctx = NodeContext::new(num, i as u32 + 1, no_nodes, algo.rounds(no_nodes),
+                                                       partial_order, left_link, right_link);
+                        if let Some(stream) = distributor {
+                            ctx.termination = Termination::Quiescence(NetStream::plain(stream));
+                        }
+                        algo.run(&mut ctx).unwrap_or_else(|e| panic!("Node {} failed : {:?}", i + 1, e))
+                    })
+                })
+                .collect();
+
+            let mut coordinated: Vec<Node> = Vec::new();
+            if is_async {
+                for _ in 0..no_nodes {
+                    let (stream, _) = listener.accept().expect("Failed to accept a node");
+                    coordinated.push(Node { port: 0, stream: NetStream::plain(stream), failure: None });
+                }
+                async_exec::coordinate_async(&mut coordinated);
+            }
+            nodes.into_iter().map(|node| node.join().expect("Node panicked")).collect()
+        })
+    }
+
+    #[test]
+    fn all_inputs_cover_every_sequence() {
+        assert_eq!(all_inputs(1), [[1]]);
+        assert_eq!(all_inputs(2), [[1, 1], [1, 2], [2, 1], [2, 2]]);
+
+        let mut inputs = all_inputs(4);
+        assert_eq!(inputs.len(), 256);
+        assert!(inputs.iter().flatten().all(|num| (1..=4).contains(num)));
+        inputs.dedup();
+        assert_eq!(inputs.len(), 256);
+    }
+
+    // every registered algorithm and order on every input of 1 up to 4 nodes,
+    // what `verify --max-nodes 4` runs on node processes
+    #[test]
+    fn small_lines_sort_every_input() {
+        for &entry in registry::entries() {
+            for partial_order in [PartialOrder::LessThan, PartialOrder::GreaterThan] {
+                for no_nodes in 1..=4 {
+                    for input_nums in all_inputs(no_nodes) {
+                        let output_nums = sort_in_process(entry, &input_nums, partial_order);
+                        assert!(verify_results(&input_nums, &output_nums, partial_order), "{} {} : {:?} -> {:?}",
+                                entry.name(), partial_order.name(), input_nums, output_nums);
+                    }
+                }
+            }
+        }
+    }
+}
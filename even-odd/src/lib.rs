@@ -0,0 +1,49 @@
+use algorithms::{Comparator, NodeContext, NodeError, OddEven, SortAlgorithm, Value};
+use utility::network::comparators;
+use utility::{get_rounds, Algo};
+
+// Experimental algorithms kept out of the algorithms crate, registered with it
+// like any other crate would. Linked into the binaries with the `extra-algos` feature
+
+// Odd-Even Transposition starting with the even pairs, n rounds like Odd-Even
+pub struct EvenOdd;
+
+algorithms::register_algo!(EvenOdd);
+
+impl SortAlgorithm for EvenOdd {
+    fn name(&self) -> &'static str {
+        "even-odd"
+    }
+
+    fn description(&self) -> &'static str {
+        "Odd Even Transposition starting with the even pairs, n rounds"
+    }
+
+    fn rounds(&self, no_nodes: u32) -> u32 {
+        get_rounds(Algo::OddEvenTransposition, no_nodes)
+    }
+
+    fn quiet_window(&self) -> u32 {
+        2
+    }
+
+    fn comparators(&self, no_nodes: u32, round: u32) -> Vec<Comparator> {
+        comparators(Algo::OddEvenTransposition, no_nodes, round + 1)
+    }
+
+    fn run(&self, ctx: &mut NodeContext) -> Result<Value, NodeError> {
+        OddEven::transposition(ctx, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use algorithms::registry;
+
+    #[test]
+    fn registered_by_its_name() {
+        let entry = registry::find("even-odd").expect("even-odd is not registered");
+        assert_eq!(entry.construct().rounds(5), 5);
+        assert_eq!(registry::names()[..4], ["odd-even", "sasaki", "triplet", "async-odd-even"]);
+    }
+}
@@ -1,132 +1,338 @@
-use std::net::{TcpListener, TcpStream};
+use std::net::TcpListener;
 use num_traits::FromPrimitive ;
 use clap::Parser;
-use std::io::{Read, Write};
+use std::io::{ErrorKind, Read, Write};
+use std::process;
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant};
+
+use utility::{CommFlags, Utility, log, OPT_CHECKSUM, OPT_DUPLEX, OPT_EARLY_VOTE, OPT_EARLY_WAVE, OPT_POOL, OPT_RELIABLE, OPT_TRACE,
+              PROTOCOL_VERSION, TOKEN_ENV, ABORT_MSG, FINISH_MSG, ORDER_MSG, REPORT_MSG};
+use utility::tls::{NetStream, TlsConfig, TLS_ENV};
+
+use algorithms::{registry, Link, NodeContext, NodeError, RelativePos, SortAlgorithm};
+// the algorithms of the crate register themselves once it is linked in
+#[cfg(feature = "extra-algos")]
+use even_odd as _;
+use algorithms::faults::{Byzantine, FaultyChannel};
+use algorithms::transport::{Channel, ChecksumChannel, NetChannel};
+use algorithms::reliable::ReliableChannel;
+use algorithms::termination::Termination;
+use utility::faults::{FaultSpec, DEFAULT_RETRANSMIT_MS};
 
-use utility::{CommFlags, Utility, log};
+struct Distributor;
+struct Neigbour;
 
-mod algos;
-mod node_utils;
+// how long a node waits for its neighbours to connect once it has the Order
+const CONNECT_TIMEOUT : Duration = Duration::from_secs(30);
 
-use node_utils::{get_rounds, Algo, Link, Node, RelativePos};
+// how long an accepted connection has to tell who it is
+const HANDSHAKE_TIMEOUT : Duration = Duration::from_secs(2);
 
-struct Distributor;
-struct Neigbour;
+// how long the accept loop sleeps when no neighbour is connecting
+const ACCEPT_WAIT : Duration = Duration::from_millis(1);
+
+// NeigbourConnect : flag + rank of the sender u32 + run id u64 + run token u64
+const CONNECT_MSG : usize = 21;
+
+// who a node is in a run, told to its neighbours when connecting
+#[derive(Clone, Copy)]
+struct Identity<'a> {
+    glb_pos : u32,
+    run_id  : u64,
+    token   : u64,
+    tls     : Option<&'a TlsConfig>,   // certificate of the run, None in the clear
+}
+
+// how the links of an order are made
+#[derive(Clone, Copy)]
+struct LinkSetup<'a> {
+    duplex   : bool,                    // one connection per neighbour pair
+    checksum : bool,                    // CRC32 on every message
+    reliable : bool,                    // messages resent until acknowledged
+    faults   : Option<&'a FaultSpec>,   // injected on the messages sent
+}
+
+impl LinkSetup<'_> {
+    // what a link is made of, a kept link is only reused for the same
+    // (the faults are the same for every order)
+    fn kind(&self) -> (bool, bool, bool) {
+        (self.duplex, self.checksum, self.reliable)
+    }
+}
+
+// what the node was started with, the same for every order
+struct Launch {
+    token  : u64,
+    seed   : Option<u64>,
+    faults : Option<FaultSpec>,
+    tls    : Option<TlsConfig>,
+}
+
+// streams to the left and right neighbours, None for a side not connected
+type SideStreams = (Option<NetStream>, Option<NetStream>);
+
+// the links of an order, along with the time spent on the TLS handshakes made
+// for them (nothing for kept links or in the clear)
+struct OrderLinks {
+    left       : Option<Link>,
+    right      : Option<Link>,
+    rel_pos    : RelativePos,
+    handshakes : Duration,
+}
+
+// Links kept from the previous run of a pool, along with the ports of the
+// neighbours they lead to (0 for none) and how they were made
+#[derive(Default)]
+struct KeptLinks {
+    l_port : u16,
+    r_port : u16,
+    kind   : (bool, bool, bool),   // duplex, checksum, reliable
+    left   : Option<Link>,
+    right  : Option<Link>,
+}
 
 
 impl Distributor {
 
     // Handles the communication with the distributor
-    pub fn handle_distributor(distributor_port: u16){
+    // a node of a pool runs one order after the other until the distributor hangs up
+    fn handle_distributor(distributor_port: u16, launch: Launch){
 
         let (listener, self_port_num) = Utility::create_server();
 
-        // Max 15 used by Order
-        let mut buffer = [0u8; 15];
-        let mut stream = Utility::connect_to_server(distributor_port);
-        let mut node_data;
+        // Order is the largest of the fixed size messages, Finish is sent from it as well
+        let mut buffer = [0u8; ORDER_MSG];
+        let mut stream = Utility::connect_to_server(distributor_port, launch.tls.as_ref());
+        let mut kept = KeptLinks::default();
     
         log!("Connected to distributor");
     
-        Self::report(self_port_num, &mut stream);
-    
-        match stream.read(&mut buffer) {
-            Ok(bytes_read) => {
-                log!("Received from distributor [{}] : {:?}", bytes_read, &buffer[..bytes_read]);
-                let cmd = buffer[0];
-                match cmd {
-                    cmd if cmd == CommFlags::Order as u8 => node_data = 
-                        Self::handle_order(&buffer[1..], listener) ,
-
-                        def_val => panic!("Invalid command : {}", def_val),
-                };
+        Self::report(self_port_num, launch.token, &mut stream);
+
+        loop {
+            // the distributor closing the stream ends the pool
+            if stream.read_exact(&mut buffer[..1]).is_err() {
+                return;
+            }
+
+            let (algorithm, mut node_data, handshakes) = match buffer[0] {
+                cmd if cmd == CommFlags::Order as u8 => {
+                    stream.read_exact(&mut buffer[1..]).expect("Failed to read order");
+                    // the name of the algorithm follows the fixed part
+                    let mut name = vec![0u8; buffer[1] as usize];
+                    stream.read_exact(&mut name).expect("Failed to read order");
+                    let name = String::from_utf8(name).expect("Algorithm name is not utf-8");
+                    log!("Received from distributor : {:?} {}", &buffer, name);
+                    match Self::handle_order(&buffer[1..], &name, &listener, &stream, &launch, &mut kept) {
+                        Ok(order) => order,
+                        // no round was run, the node still holds the number of the order
+                        Err(e) => {
+                            let num = i32::from_le_bytes(buffer[15..19].try_into().unwrap());
+                            Self::abort(e, 0, num, &mut stream);
+                            return;
+                        },
+                    }
+                },
+                cmd if cmd == CommFlags::Reset as u8 => {
+                    log!("Reset, dropping the links");
+                    kept = KeptLinks::default();
+                    continue;
+                },
+                def_val => panic!("Invalid command : {}", def_val),
+            };
+            let pooled = buffer[19] & OPT_POOL != 0;
+
+            let num = match Self::start_sorting(algorithm.as_ref(), &mut node_data) {
+                Ok(num) => num,
+                // crash-stop : the connections drop without a word
+                Err(NodeError::Crashed) => process::exit(1),
+                // nobody left to report to
+                Err(NodeError::DistributorLost) => {
+                    eprintln!("Node {} : lost the distributor in round {}", node_data.glb_pos, node_data.rounds_used + 1);
+                    return;
+                },
+                Err(e) => {
+                    Self::abort(e, node_data.rounds_used + 1, node_data.num, &mut stream);
+                    return;
+                },
+            };
+
+            buffer[0] = CommFlags::Finish as u8;
+            buffer[1..5].copy_from_slice(&num.to_le_bytes());
+            buffer[5..9].copy_from_slice(&node_data.rounds_used.to_le_bytes());
+            buffer[9..13].copy_from_slice(&node_data.exchange_msgs.to_le_bytes());
+            buffer[13..17].copy_from_slice(&node_data.control_msgs.to_le_bytes());
+            buffer[17..21].copy_from_slice(&node_data.resent_msgs().to_le_bytes());
+            let handshakes_us = u32::try_from(handshakes.as_micros()).unwrap_or(u32::MAX);
+            buffer[21..25].copy_from_slice(&handshakes_us.to_le_bytes());
+            assert_eq!(stream.write(&buffer[..FINISH_MSG]).expect("Failed to send msg"), FINISH_MSG);
+
+            if let Some(trace) = node_data.trace.as_ref() {
+                Self::send_trace(trace, &mut stream);
+            }
+
+            if !pooled {
+                return;
             }
-            Err(e) => panic!("Failed to read : {}", e),
+            kept.left = node_data.left_link.take();
+            kept.right = node_data.right_link.take();
         }
+    }
 
-        buffer[0] = CommFlags::Finish as u8;
-        buffer[1..5].copy_from_slice(
-                &Self::start_sorting(&mut node_data).to_le_bytes()
-        );
-        assert_eq!(stream.write(&buffer[..5]).expect("Failed to send msg"), 5);
+    // tells the distributor sorting could not finish because of a neighbour,
+    // along with the round it happened in (0 before the first) and the value the node held
+    fn abort(error: NodeError, round: u32, num: i32, stream: &mut NetStream) {
+        log!("Aborting in round {} : {:?}", round, error);
+        let (reason, side) = match error {
+            NodeError::NeighbourLost(side)  => (0, side),
+            NodeError::Timeout(side)        => (1, side),
+            NodeError::Protocol(side)       => (2, side),
+            NodeError::NeverConnected(side) => (3, side),
+            NodeError::Crashed              => unreachable!("A crashed node does not abort"),
+            NodeError::DistributorLost      => unreachable!("Nothing to abort to without the distributor"),
+        };
+        let mut buffer = [0u8; ABORT_MSG];
+        buffer[0] = CommFlags::Abort as u8;
+        buffer[1] = reason;
+        buffer[2] = side as u8;
+        buffer[3..7].copy_from_slice(&round.to_le_bytes());
+        buffer[7..11].copy_from_slice(&num.to_le_bytes());
+        // the distributor may be gone as well, nothing left to do about it
+        let _ = stream.write_all(&buffer);
+    }
+
+    // sends the value held after each round, follows Finish when tracing is requested
+    fn send_trace(trace: &[i32], stream: &mut NetStream) {
+        let mut buffer = Vec::with_capacity(5 + trace.len() * 4);
+        buffer.push(CommFlags::Trace as u8);
+        buffer.extend_from_slice(&(trace.len() as u32).to_le_bytes());
+        for num in trace {
+            buffer.extend_from_slice(&num.to_le_bytes());
+        }
+        stream.write_all(&buffer).expect("Failed to send trace");
     }
 
-    // reports to the Distributor about its presence and its port num
-    fn report(node_port: u16, stream: &mut TcpStream) {
-        let mut buffer= [0u8; 3];
+    // reports to the Distributor about its presence, the protocol it speaks, its port num
+    // and the run token it was started with
+    fn report(node_port: u16, token: u64, stream: &mut NetStream) {
+        let mut buffer= [0u8; REPORT_MSG];
         buffer[0] = CommFlags::Report as u8;
-        buffer[1..].copy_from_slice(&node_port.to_le_bytes());
-        assert_eq!(stream.write(&buffer).expect("Failed to report to distributor"), 3);
+        buffer[1] = PROTOCOL_VERSION;
+        buffer[2..4].copy_from_slice(&node_port.to_le_bytes());
+        buffer[4..12].copy_from_slice(&token.to_le_bytes());
+        stream.write_all(&buffer).expect("Failed to report to distributor");
     }
 
-    fn handle_order(buffer: &[u8], listener:TcpListener) -> Node {
-        if buffer.len() != 14 {
+    fn handle_order(buffer: &[u8], algo: &str, listener:&TcpListener, stream: &NetStream, 
+                    launch: &Launch, kept: &mut KeptLinks) 
+                    -> Result<(Box<dyn SortAlgorithm>, NodeContext, Duration), NodeError> {
+        let faults = launch.faults.as_ref();
+        if buffer.len() != ORDER_MSG - 1 {
             panic!("Invalid order : {:?}", buffer);
         }
     
         else {
 
-            let algo = buffer[0];
             let partial_order = buffer[1];
-            let no_nodes = &buffer[2..4];
-            let l_port = &buffer[4..6];
-            let r_port = &buffer[6..8];
-            let glb_pos = &buffer[8..10];
-            let num = &buffer[10..14];
-
-
-            let algo = FromPrimitive::from_u8(algo)
-                .expect(&format!("Unknown algo {} (0 : Odd-Even | 1 : Sasaki | 2 : Triplet)", algo));
+            let no_nodes = &buffer[2..6];
+            let l_port = &buffer[6..8];
+            let r_port = &buffer[8..10];
+            let glb_pos = &buffer[10..14];
+            let num = &buffer[14..18];
+            let options = buffer[18];
+            let crash_round = u32::from_le_bytes(buffer[19..23].try_into().unwrap());
+            let byzantine = buffer[23];
+            let run_id = u64::from_le_bytes(buffer[24..32].try_into().unwrap());
+
+
+            let algorithm = registry::lookup(algo).unwrap_or_else(|| 
+                panic!("Unknown algorithm '{}' ({}), build the node with the features of the distributor", 
+                       algo, registry::names().join(" | ")));
         
             let partial_order = FromPrimitive::from_u8(partial_order)
-                .expect(&format!("Unknow partial order {} (0 : LessThan | 1 : GreaterThan)", partial_order));
+                .unwrap_or_else(|| panic!("Unknow partial order {} (0 : LessThan | 1 : GreaterThan)", partial_order));
 
-            let no_nodes = u16::from_le_bytes(
+            let no_nodes = u32::from_le_bytes(
                 no_nodes.try_into()
-                .expect(&format!("Failed to parse {:?} into u16", no_nodes
+                .unwrap_or_else(|_| panic!("Failed to parse {:?} into u32", no_nodes
             )));
 
             let l_port = u16::from_le_bytes(
                 l_port.try_into()
-                .expect(&format!("Failed to parse {:?} into u16", l_port
+                .unwrap_or_else(|_| panic!("Failed to parse {:?} into u16", l_port
             )));
 
             let r_port = u16::from_le_bytes(
                 r_port.try_into()
-                .expect(&format!("Failed to parse {:?} into u16", r_port
+                .unwrap_or_else(|_| panic!("Failed to parse {:?} into u16", r_port
             )));
 
-            let glb_pos = u16::from_le_bytes(
+            let glb_pos = u32::from_le_bytes(
                 glb_pos.try_into()
-                .expect(&format!("Failed to parse {:?} into u16", glb_pos
+                .unwrap_or_else(|_| panic!("Failed to parse {:?} into u32", glb_pos
             )));
 
 
             let num = i32::from_le_bytes(
                 num.try_into()
-                .expect(&format!("Failed to parse {:?} into i32", num
+                .unwrap_or_else(|_| panic!("Failed to parse {:?} into i32", num
             )));
 
 
-            assert!(!(l_port == 0 && r_port == 0));
-            assert!(no_nodes != 0);
+            // 0 : honest, else the misbehaviour + 1
+            let byzantine = match byzantine {
+                0 => Byzantine::honest(),
+                mode => {
+                    let mode = FromPrimitive::from_u8(mode - 1)
+                        .unwrap_or_else(|| panic!("Unknown byzantine mode {}", mode));
+                    Byzantine::new(mode, launch.seed.unwrap_or_else(rand::random) ^ glb_pos as u64)
+                },
+            };
 
-            let rounds = get_rounds(algo, no_nodes);
-            let (left_link, right_link, rel_pos) = Neigbour::get_links_rel_pos(listener, l_port, r_port);
+            assert!(no_nodes != 0);
+            assert!((l_port == 0 && r_port == 0) == (no_nodes == 1), "Only a single node has no neighbours");
+
+            let rounds = algorithm.rounds(no_nodes);
+            let setup = LinkSetup { duplex   : options & OPT_DUPLEX != 0,
+                                    checksum : options & OPT_CHECKSUM != 0,
+                                    reliable : options & OPT_RELIABLE != 0,
+                                    faults };
+            let identity = Identity { glb_pos, run_id, token: launch.token, tls: launch.tls.as_ref() };
+            let OrderLinks { left: left_link, right: right_link, rel_pos, handshakes } = 
+                Neigbour::get_links_rel_pos(listener, l_port, r_port, identity, setup, kept)?;
+            
+            let trace = if options & OPT_TRACE != 0 { Some(Vec::with_capacity(rounds as usize)) } else { None };
+            let termination = if algorithm.is_async() {
+                Termination::Quiescence(stream.try_clone().expect("Failed to clone distributor stream"))
+            } else if options & OPT_EARLY_VOTE != 0 {
+                Termination::Vote(stream.try_clone().expect("Failed to clone distributor stream"))
+            } else if options & OPT_EARLY_WAVE != 0 {
+                Termination::Wave { window: algorithm.quiet_window(), quiet_rounds: 0 }
+            } else {
+                Termination::Fixed
+            };
             
-            Node {algo, partial_order, left_link, right_link, rounds, rel_pos, glb_pos, num}
+            Ok((algorithm,
+                NodeContext {partial_order, left_link, right_link, rounds, no_nodes, rel_pos, glb_pos, num, trace, 
+                     termination, rounds_used: 0, exchange_msgs: 0, control_msgs: 0,
+                     crash_round: if crash_round == 0 { None } else { Some(crash_round) },
+                     timeout: faults.map(|faults| faults.timeout()), byzantine},
+                handshakes))
         }
     }
 
-    fn start_sorting(node_data:&mut Node) -> i32 {
+    fn start_sorting(algorithm: &dyn SortAlgorithm, node_data:&mut NodeContext) -> Result<i32, NodeError> {
 
-        assert_ne!(node_data.rounds, 0); 
-
-        match node_data.algo {
-            Algo::OddEvenTransposition => algos::OddEven::odd_even_transposition(node_data),
-            Algo::Sasaki               => algos::Sasaki::sasaki(node_data),
-            Algo::Triplet              => algos::Triplet::triplet(node_data),
+        // a single node already holds the sorted line
+        if node_data.rel_pos == RelativePos::Alone {
+            return Ok(node_data.num);
         }
+
+        log!("Sorting with {}", algorithm.name());
+        let num = algorithm.run(node_data)?;
+        node_data.flush_links()?;
+        Ok(num)
     }
 
 
@@ -135,145 +341,218 @@ impl Distributor {
 
 impl Neigbour {
 
-    fn get_read_streams(listener: TcpListener, rel_pos:RelativePos) -> (Option<TcpStream>, Option<TcpStream>) {
-        let mut no_clients:u8 = 0;
-        let max_clients:u8 = match rel_pos {
-            RelativePos::Middle => 2,
-            _ => 1
-        };
-        let mut buffer = [0u8; 2];
+    // accepts the connections of the neighbours on the sides asked for, every neighbour
+    // tells its rank and the run it belongs to. Anything else connecting is dropped.
+    // A neighbour still missing after CONNECT_TIMEOUT is given up on.
+    // Returns the time spent on the TLS handshakes of the neighbours kept
+    fn get_read_streams(listener: &TcpListener, identity: Identity, need_left: bool, need_right: bool) 
+        -> Result<(SideStreams, Duration), NodeError> {
         let mut l_read = None;
         let mut r_read = None;
-
-        while no_clients != max_clients {
-            for stream in listener.incoming() { 
-                match stream {
-                    Ok(mut stream) => {
-                        no_clients += 1;
-                        match stream.read(&mut buffer) {
-                            Ok(bytes_read) => {
-
-                                log!("Received from neigbour : {:?}", &buffer[..bytes_read]);
-                                assert_eq!(bytes_read, 2);
-                                assert_eq!(buffer[0], CommFlags::NeigbourConnect as u8);
-    
-                                let claimed_pos = buffer[1];
-
-                                match claimed_pos {
-
-                                    claimed_pos if claimed_pos == RelativePos::Left as u8 => {
-                                        assert_ne!(rel_pos, RelativePos::Left);
-                                        l_read = Some(stream);
-                                        if rel_pos == RelativePos::Right || 
-                                           r_read.is_some() {
-                                            return (l_read, r_read);
-                                        }
-                                    },
-
-                                    claimed_pos if claimed_pos == RelativePos::Right as u8 => {
-                                        assert_ne!(rel_pos, RelativePos::Right);
-                                        r_read = Some(stream);
-                                        if rel_pos == RelativePos::Left || 
-                                           l_read.is_some() {
-                                           return (l_read, r_read);
-                                        }
-                                    },
-
-                                    def_val => panic!("Unexpected value {}", def_val)
-                                }
-                            },
-                            Err(e) => panic!("Error : {}", e),
-                        }
-                    },
-                    Err(e) => panic!("Error : {}",e),
-                }
+        let mut handshakes = Duration::ZERO;
+        let deadline = Instant::now() + CONNECT_TIMEOUT;
+
+        listener.set_nonblocking(true).expect("Failed to set non-blocking");
+        while l_read.is_some() != need_left || r_read.is_some() != need_right {
+            let mut stream = match Utility::accept(listener, identity.tls) {
+                Ok(stream) => stream,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        listener.set_nonblocking(false).expect("Failed to set blocking");
+                        let side = if l_read.is_none() && need_left { RelativePos::Left } else { RelativePos::Right };
+                        log!("{:?} neighbour did not connect within {:?}", side, CONNECT_TIMEOUT);
+                        return Err(NodeError::NeverConnected(side));
+                    }
+                    sleep(ACCEPT_WAIT);
+                    continue;
+                },
+                Err(e) => panic!("Error : {}", e),
+            };
+
+            match Self::identify(&mut stream, identity) {
+                Ok((rank, handshake)) if need_left && l_read.is_none() && rank.checked_add(1) == Some(identity.glb_pos) => {
+                    l_read = Some(stream);
+                    handshakes += handshake;
+                },
+                Ok((rank, handshake)) if need_right && r_read.is_none() && rank == identity.glb_pos.wrapping_add(1) => {
+                    r_read = Some(stream);
+                    handshakes += handshake;
+                },
+                Ok((rank, _)) => eprintln!("Node {} : Rejected a connection from node {}, not an expected neighbour",
+                                           identity.glb_pos, rank),
+                Err(reason) => eprintln!("Node {} : Rejected a connection : {}", identity.glb_pos, reason),
             }
         }
-        (l_read, r_read)
+        listener.set_nonblocking(false).expect("Failed to set blocking");
+        Ok(((l_read, r_read), handshakes))
     }
 
-
-
-    // Connects to nieghbour nodes and returns the streams
-    // These streams are used to send data to the neighbours
-    // Called by handle_distributor immediately after receiving 
-    // order (CommFlags::Order) from the distributor
-    fn get_write_streams(l_port:u16, r_port:u16) -> 
-        (Option<TcpStream>, Option<TcpStream>, RelativePos) {
-        
-        let mut l_stream;
-        let mut r_stream;
-        let rel_pos;
-        let mut buffer = [0u8; 2];
-        buffer[0] = CommFlags::NeigbourConnect as u8;
-
-        if l_port == 0 && r_port == 0 {
-            panic!("Both ports cannot be zero !!");
+    // reads the NeigbourConnect an accepted connection starts with, returns the rank it claims
+    // and the time its TLS handshake took, done apart so it can be timed (zero in the clear)
+    fn identify(stream: &mut NetStream, identity: Identity) -> Result<(u32, Duration), String> {
+        let mut buffer = [0u8; CONNECT_MSG];
+        stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).map_err(|e| e.to_string())?;
+        let mut handshake = Duration::ZERO;
+        if identity.tls.is_some() {
+            let handshake_started = Instant::now();
+            stream.handshake().map_err(|e| format!("TLS handshake failed ({})", e))?;
+            handshake = handshake_started.elapsed();
         }
+        stream.read_exact(&mut buffer).map_err(|e| format!("no handshake ({})", e))?;
+        stream.set_read_timeout(None).map_err(|e| e.to_string())?;
 
-        // && r_port != 0
-        if l_port == 0 {
-            rel_pos = RelativePos::Left;
-            r_stream = Some(Utility::connect_to_server(r_port));
-            l_stream = None;
+        log!("Received from neigbour : {:?}", buffer);
+        if buffer[0] != CommFlags::NeigbourConnect as u8 {
+            return Err(format!("unexpected message {}", buffer[0]));
         }
-
-        // && l_port != 0
-        else if r_port == 0 {
-            rel_pos = RelativePos::Right;
-            l_stream = Some(Utility::connect_to_server(l_port));
-            r_stream = None;
+        if u64::from_le_bytes(buffer[13..21].try_into().unwrap()) != identity.token {
+            return Err("wrong run token".to_string());
         }
-
-        else {
-            rel_pos = RelativePos::Middle;
-            l_stream = Some(Utility::connect_to_server(l_port));
-            r_stream = Some(Utility::connect_to_server(r_port));
+        let claimed_run = u64::from_le_bytes(buffer[5..13].try_into().unwrap());
+        if claimed_run != identity.run_id {
+            return Err(format!("run {} instead of {}", claimed_run, identity.run_id));
         }
+        Ok((u32::from_le_bytes(buffer[1..5].try_into().unwrap()), handshake))
+    }
 
-        // if l_stream is not none, i.e if left neighbour is available
-        // send the connect message
-        if let Some(ref mut stream) = l_stream {
-
-            // have to report to self left neighbour as its right neighbour
-            buffer[1] = RelativePos::Right as u8;
-            assert_eq!(stream.write(&buffer)
-                .expect(&format!("Failed to send the message")), 2);
+    // position of the node in the line from the ports of its neighbours
+    fn rel_pos(l_port:u16, r_port:u16) -> RelativePos {
+        match (l_port, r_port) {
+            (0, 0) => RelativePos::Alone,
+            (0, _) => RelativePos::Left,
+            (_, 0) => RelativePos::Right,
+            _      => RelativePos::Middle,
         }
+    }
 
-        // if r_stream is not none, i.e if right neighbout is available
-        // send the connect message
-        if let Some(ref mut stream) = r_stream {
-
-            // have to report to self right neighbout as its left neighbour
-            buffer[1] = RelativePos::Left as u8;
-            assert_eq!(stream.write(&buffer)
-                .expect(&format!("Failed to send the message")), 2);
-        }
+    // Connects to nieghbour nodes and returns the streams
+    // These streams are used to send data to the neighbours
+    // Called by handle_distributor immediately after receiving 
+    // order (CommFlags::Order) from the distributor, 0 skips a side
+    // every neighbour is told the rank of this node and the run it belongs to
+    // returns the time spent connecting over TLS, handshakes included
+    fn get_write_streams(l_port:u16, r_port:u16, identity: Identity) -> Result<(SideStreams, Duration), NodeError> {
+        
+        let mut buffer = [0u8; CONNECT_MSG];
+        buffer[0] = CommFlags::NeigbourConnect as u8;
+        buffer[1..5].copy_from_slice(&identity.glb_pos.to_le_bytes());
+        buffer[5..13].copy_from_slice(&identity.run_id.to_le_bytes());
+        buffer[13..21].copy_from_slice(&identity.token.to_le_bytes());
+
+        // a neighbour that cannot be reached, or drops the connect message, is gone before the rounds
+        let mut handshakes = Duration::ZERO;
+        let mut connect = |port: u16, side: RelativePos| -> Result<Option<NetStream>, NodeError> {
+            if port == 0 {
+                return Ok(None);
+            }
+            let connect_started = Instant::now();
+            let stream = Utility::try_connect_to_server(port, identity.tls);
+            if identity.tls.is_some() {
+                handshakes += connect_started.elapsed();
+            }
+            let mut stream = stream
+                .and_then(|mut stream| stream.write_all(&buffer).map(|_| stream))
+                .map_err(|e| {
+                    log!("Failed to connect to the {:?} neighbour : {}", side, e);
+                    NodeError::NeverConnected(side)
+                })?;
+            stream.flush().map_err(|_| NodeError::NeverConnected(side))?;
+            Ok(Some(stream))
+        };
 
-        (l_stream, r_stream, rel_pos)
+        let streams = (connect(l_port, RelativePos::Left)?, connect(r_port, RelativePos::Right)?);
+        Ok((streams, handshakes))
     }
 
-    fn get_links_rel_pos(listener: TcpListener, l_port:u16, r_port:u16) 
-    -> (Option<Link>, Option<Link>, RelativePos) {
-        let (l_write_stream, r_write_stream, rel_pos) = Neigbour::get_write_streams(l_port, r_port);
-        let (l_read_stream, r_read_stream) = Neigbour::get_read_streams(listener, rel_pos);
+    // A link kept from the previous run of a pool is reused when it leads to the
+    // same neighbour and is made the same way, the neighbour keeps its end as well
+    // as it gets the same order. Others are reconnected.
+    // Duplex links are one connection both ways, made by the left node of the pair
+    fn get_links_rel_pos(listener: &TcpListener, l_port:u16, r_port:u16, identity: Identity, setup: LinkSetup,
+                         kept: &mut KeptLinks) -> Result<OrderLinks, NodeError> {
+        let glb_pos = identity.glb_pos;
+        let rel_pos = Neigbour::rel_pos(l_port, r_port);
+        let same_kind = kept.kind == setup.kind();
+        let l_kept = kept.left.take().filter(|_| same_kind && l_port != 0 && kept.l_port == l_port);
+        let r_kept = kept.right.take().filter(|_| same_kind && r_port != 0 && kept.r_port == r_port);
+        (kept.l_port, kept.r_port, kept.kind) = (l_port, r_port, setup.kind());
+
+        let l_connect = if l_kept.is_none() { l_port } else { 0 };
+        let r_connect = if r_kept.is_none() { r_port } else { 0 };
+
+        if setup.duplex {
+            let ((_, r_stream), (l_stream, _), handshakes) = Self::connect_and_accept(listener, 0, r_connect, identity,
+                                                                                      l_connect != 0, false)?;
+            let l_link = l_stream.map(|stream| Self::make_duplex_link(stream, RelativePos::Left, glb_pos - 1, identity, setup));
+            let r_link = r_stream.map(|stream| Self::make_duplex_link(stream, RelativePos::Right, glb_pos, identity, setup));
+            return Ok(OrderLinks { left: l_link.or(l_kept), right: r_link.or(r_kept), rel_pos, handshakes });
+        }
+
+        let ((l_write_stream, r_write_stream), (l_read_stream, r_read_stream), handshakes) = 
+            Self::connect_and_accept(listener, l_connect, r_connect, identity, l_connect != 0, r_connect != 0)?;
         
         let l_link = if let (Some(write_stream), Some(read_stream)) = (l_write_stream, l_read_stream) {
-            Some(Link{write_stream, read_stream})
+            Some(Self::make_link(write_stream, read_stream, RelativePos::Left, glb_pos - 1, identity, setup))
         }
         else {
-            None
+            l_kept
         };
 
         let r_link = if let (Some(write_stream), Some(read_stream)) = (r_write_stream, r_read_stream) {
-            Some(Link{write_stream, read_stream})
+            Some(Self::make_link(write_stream, read_stream, RelativePos::Right, glb_pos, identity, setup))
         }
         else {
-            None
+            r_kept
         };
 
-        (l_link, r_link, rel_pos)
+        Ok(OrderLinks { left: l_link, right: r_link, rel_pos, handshakes })
+    }
+
+    // connects to the neighbours while accepting theirs, over TLS connecting only
+    // returns once the neighbour has accepted and the handshake is done, along with the
+    // time spent on the handshakes of both sides (summed, though they overlap)
+    fn connect_and_accept(listener: &TcpListener, l_port: u16, r_port: u16, identity: Identity,
+                          need_left: bool, need_right: bool) 
+                          -> Result<(SideStreams, SideStreams, Duration), NodeError> {
+        thread::scope(|scope| {
+            let accepting = scope.spawn(|| Self::get_read_streams(listener, identity, need_left, need_right));
+            let write_streams = Self::get_write_streams(l_port, r_port, identity);
+            let read_streams = accepting.join().unwrap_or_else(|e| std::panic::resume_unwind(e));
+            let ((write_streams, connecting), (read_streams, accepting)) = (write_streams?, read_streams?);
+            Ok((write_streams, read_streams, connecting + accepting))
+        })
+    }
+
+    // wraps the channel to inject the faults configured for the edge, to check the
+    // messages for corruption (including the injected one) and to resend the lost ones
+    // when asked for. With faults a neighbour may never answer, it is given up on after
+    // the timeout, over a reliable link as well
+    fn make_link(write_stream: NetStream, read_stream: NetStream, side: RelativePos, edge: u32, 
+                 identity: Identity, setup: LinkSetup) -> Link {
+        let mut channel: Box<dyn Channel> = Box::new(NetChannel::with_token(write_stream, read_stream, identity.token));
+        if let Some(faults) = setup.faults {
+            let seed = faults.seed.unwrap_or(0) ^ ((identity.glb_pos as u64) << 1 | side as u64);
+            channel = FaultyChannel::wrap(channel, faults.for_edge(edge), seed);
+        }
+        if setup.checksum {
+            channel = ChecksumChannel::wrap(channel);
+        }
+        if setup.reliable {
+            let retransmit = setup.faults.map_or(Duration::from_millis(DEFAULT_RETRANSMIT_MS), 
+                                                 |faults| faults.retransmit());
+            channel = ReliableChannel::wrap(channel, retransmit).expect("Failed to set read timeout");
+        }
+        if let Some(faults) = setup.faults {
+            channel.set_timeout(Some(faults.timeout())).expect("Failed to set read timeout");
+        }
+        Link::new(channel, side)
+    }
+
+    fn make_duplex_link(stream: NetStream, side: RelativePos, edge: u32, identity: Identity,
+                        setup: LinkSetup) -> Link {
+        let write_stream = stream.try_clone().expect("Failed to clone neighbour stream");
+        Self::make_link(write_stream, stream, side, edge, identity, setup)
     }
 
     
@@ -293,9 +572,28 @@ impl Neigbour {
 struct Args {
     #[arg(short, long, help = "Enter the distributor port (u16)")]
     dist_port : u16,
+
+    #[arg(long, env = TOKEN_ENV, hide_env_values = true,
+        help = "Run token, handed over by the distributor in the environment")]
+    token : u64,
+
+    #[arg(long, help = "Seed of the run, handed over by the distributor")]
+    seed : Option<u64>,
+
+    #[arg(long, help = "Faults to inject on the neighbour links, handed over by the distributor")]
+    faults : Option<String>,
+
+    #[arg(long, env = TLS_ENV, hide_env_values = true,
+        help = "PEM bundle of the run (CA, certificate, key) to use TLS, handed over by the distributor in the environment")]
+    tls : Option<String>,
 }
 
 fn main() {
     // Receive distributor port from the terminal
-    Distributor::handle_distributor(Args::parse().dist_port);
+    let args = Args::parse();
+    let faults = args.faults.map(|spec| FaultSpec::parse(&spec)
+        .unwrap_or_else(|e| panic!("Invalid fault spec '{}' : {}", spec, e)));
+    let tls = args.tls.map(|bundle| TlsConfig::from_pem(&bundle)
+        .unwrap_or_else(|e| panic!("Invalid TLS bundle : {}", e)));
+    Distributor::handle_distributor(args.dist_port, Launch { token: args.token, seed: args.seed, faults, tls });
 }
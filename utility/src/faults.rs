@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+use num_derive::FromPrimitive;
+
+// Faults injected on the messages a node sends over its neighbour links.
+//
+// A spec is a list of entries separated by ';' (or new lines in a config file),
+// every entry a comma separated list of key=value :
+//     drop=0.1                   probability a message is silently dropped
+//     delay=fixed:5              delay before sending, in ms (fixed:MS | uniform:LO:HI | exp:MEAN)
+//     reorder=0.05               probability a message is held back and sent after the next one
+//     corrupt=0.01               probability a bit of a message is flipped on the way
+//     timeout=3000               ms a node waits for a neighbour before giving up
+//     retransmit=50              ms a reliable link waits for an ack before sending again
+//     seed=42                    seed of every node's random generator, for reproducible runs
+//     edge=3                     restricts the entry to the edge between node 3 and node 4
+// Entries without an edge apply to every edge, later entries override earlier ones.
+// e.g. "drop=0.01,seed=7; edge=3,delay=uniform:1:20"
+
+pub const DEFAULT_TIMEOUT_MS : u64 = 3000;
+pub const DEFAULT_RETRANSMIT_MS : u64 = 50;
+
+// How a Byzantine node misbehaves in the exchanges of a round based algorithm
+#[derive(FromPrimitive, Debug, Clone, Copy, PartialEq)]
+pub enum ByzantineMode {
+    Lie,            // sends the same made up value to both neighbours, a new one every round
+    Inconsistent,   // sends a different made up value to each neighbour
+    Refuse,         // tells the truth but never takes a value from a neighbour
+}
+
+impl ByzantineMode {
+    pub fn from_name(name: &str) -> Option<ByzantineMode> {
+        match name {
+            "lie"          => Some(ByzantineMode::Lie),
+            "inconsistent" => Some(ByzantineMode::Inconsistent),
+            "refuse"       => Some(ByzantineMode::Refuse),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ByzantineMode::Lie          => "lie",
+            ByzantineMode::Inconsistent => "inconsistent",
+            ByzantineMode::Refuse       => "refuse",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Delay {
+    Fixed(u64),
+    Uniform(u64, u64),
+    Exponential(u64),   // mean
+}
+
+// faults on one edge, None for the ones not given
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EdgeFaults {
+    pub drop    : Option<f64>,
+    pub delay   : Option<Delay>,
+    pub reorder : Option<f64>,
+    pub corrupt : Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FaultSpec {
+    pub seed          : Option<u64>,
+    pub timeout_ms    : Option<u64>,
+    pub retransmit_ms : Option<u64>,
+    pub all_edges     : EdgeFaults,
+    pub edges         : BTreeMap<u32, EdgeFaults>,   // keyed by the left node of the edge
+}
+
+impl EdgeFaults {
+    // values given in `other` override the ones in self
+    fn merge(&mut self, other: &EdgeFaults) {
+        self.drop    = other.drop.or(self.drop);
+        self.delay   = other.delay.or(self.delay);
+        self.reorder = other.reorder.or(self.reorder);
+        self.corrupt = other.corrupt.or(self.corrupt);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == EdgeFaults::default()
+    }
+}
+
+fn parse_probability(key: &str, value: &str) -> Result<f64, String> {
+    let prob = value.parse::<f64>().map_err(|e| format!("Invalid {} '{}' : {}", key, value, e))?;
+    if !(0.0..=1.0).contains(&prob) {
+        return Err(format!("{} has to be between 0 and 1, got {}", key, prob));
+    }
+    Ok(prob)
+}
+
+fn parse_ms(value: &str) -> Result<u64, String> {
+    value.parse::<u64>().map_err(|e| format!("Invalid duration '{}' : {}", value, e))
+}
+
+fn parse_delay(value: &str) -> Result<Delay, String> {
+    let parts: Vec<&str> = value.split(':').collect();
+    match parts.as_slice() {
+        ["fixed", ms]         => Ok(Delay::Fixed(parse_ms(ms)?)),
+        ["uniform", low, high] => {
+            let (low, high) = (parse_ms(low)?, parse_ms(high)?);
+            if low > high {
+                return Err(format!("Empty delay range {}..{}", low, high));
+            }
+            Ok(Delay::Uniform(low, high))
+        },
+        ["exp", mean]         => Ok(Delay::Exponential(parse_ms(mean)?)),
+        _ => Err(format!("Invalid delay '{}' (fixed:MS | uniform:LO:HI | exp:MEAN)", value)),
+    }
+}
+
+impl FaultSpec {
+    pub fn parse(spec: &str) -> Result<FaultSpec, String> {
+        let mut fault_spec = FaultSpec::default();
+
+        for entry in spec.split([';', '\n']) {
+            // comments are allowed in config files
+            let entry = entry.split('#').next().unwrap_or("").trim();
+            if entry.is_empty() {
+                continue;
+            }
+
+            let mut edge = None;
+            let mut faults = EdgeFaults::default();
+
+            for pair in entry.split(',') {
+                let (key, value) = pair.split_once('=')
+                    .ok_or(format!("Expected key=value, got '{}'", pair.trim()))?;
+                let (key, value) = (key.trim(), value.trim());
+
+                match key {
+                    "drop"    => faults.drop = Some(parse_probability(key, value)?),
+                    "reorder" => faults.reorder = Some(parse_probability(key, value)?),
+                    "corrupt" => faults.corrupt = Some(parse_probability(key, value)?),
+                    "delay"   => faults.delay = Some(parse_delay(value)?),
+                    "timeout" => fault_spec.timeout_ms = Some(parse_ms(value)?),
+                    "retransmit" => fault_spec.retransmit_ms = Some(parse_ms(value)?),
+                    "seed"    => fault_spec.seed = Some(value.parse::<u64>()
+                                     .map_err(|e| format!("Invalid seed '{}' : {}", value, e))?),
+                    "edge"    => edge = Some(value.parse::<u32>()
+                                     .map_err(|e| format!("Invalid edge '{}' : {}", value, e))?),
+                    def_val   => return Err(format!("Unknown fault '{}'", def_val)),
+                }
+            }
+
+            match edge {
+                Some(0) => return Err("Edges are counted from 1".to_string()),
+                Some(edge) => fault_spec.edges.entry(edge).or_default().merge(&faults),
+                None => fault_spec.all_edges.merge(&faults),
+            }
+        }
+        Ok(fault_spec)
+    }
+
+    // faults on the edge between node `edge` and node `edge + 1`
+    pub fn for_edge(&self, edge: u32) -> EdgeFaults {
+        let mut faults = self.all_edges;
+        if let Some(edge_faults) = self.edges.get(&edge) {
+            faults.merge(edge_faults);
+        }
+        faults
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS))
+    }
+
+    pub fn retransmit(&self) -> Duration {
+        Duration::from_millis(self.retransmit_ms.unwrap_or(DEFAULT_RETRANSMIT_MS))
+    }
+
+    // nothing injected and the timings left to their defaults, the seed alone changes nothing
+    pub fn is_empty(&self) -> bool {
+        self.timeout_ms.is_none() && self.retransmit_ms.is_none()
+            && self.all_edges.is_empty() && self.edges.values().all(|faults| faults.is_empty())
+    }
+}
+
+impl fmt::Display for Delay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Delay::Fixed(ms)          => write!(f, "fixed:{}", ms),
+            Delay::Uniform(low, high) => write!(f, "uniform:{}:{}", low, high),
+            Delay::Exponential(mean)  => write!(f, "exp:{}", mean),
+        }
+    }
+}
+
+impl fmt::Display for EdgeFaults {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut pairs = Vec::new();
+        if let Some(drop) = self.drop {
+            pairs.push(format!("drop={}", drop));
+        }
+        if let Some(delay) = self.delay {
+            pairs.push(format!("delay={}", delay));
+        }
+        if let Some(reorder) = self.reorder {
+            pairs.push(format!("reorder={}", reorder));
+        }
+        if let Some(corrupt) = self.corrupt {
+            pairs.push(format!("corrupt={}", corrupt));
+        }
+        write!(f, "{}", pairs.join(","))
+    }
+}
+
+// Serialises back into a spec, used to hand the distributor's spec over to the nodes
+impl fmt::Display for FaultSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut entries = Vec::new();
+
+        let mut global = Vec::new();
+        if let Some(seed) = self.seed {
+            global.push(format!("seed={}", seed));
+        }
+        if let Some(timeout_ms) = self.timeout_ms {
+            global.push(format!("timeout={}", timeout_ms));
+        }
+        if let Some(retransmit_ms) = self.retransmit_ms {
+            global.push(format!("retransmit={}", retransmit_ms));
+        }
+        if !self.all_edges.is_empty() {
+            global.push(self.all_edges.to_string());
+        }
+        if !global.is_empty() {
+            entries.push(global.join(","));
+        }
+
+        for (edge, faults) in &self.edges {
+            if !faults.is_empty() {
+                entries.push(format!("edge={},{}", edge, faults));
+            }
+        }
+        write!(f, "{}", entries.join(";"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timings_alone_are_not_empty() {
+        assert!(FaultSpec::parse("").unwrap().is_empty());
+        assert!(FaultSpec::parse("seed=7").unwrap().is_empty());
+        assert!(!FaultSpec::parse("timeout=9000").unwrap().is_empty());
+        assert!(!FaultSpec::parse("retransmit=400").unwrap().is_empty());
+        assert!(!FaultSpec::parse("edge=2,drop=0.1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn written_spec_parses_back() {
+        let spec = FaultSpec::parse("retransmit=400,timeout=9000; drop=0.01,delay=uniform:0:5; edge=3,reorder=0.2")
+            .unwrap();
+        assert_eq!(spec.timeout(), Duration::from_millis(9000));
+        assert_eq!(spec.retransmit(), Duration::from_millis(400));
+        assert_eq!(FaultSpec::parse(&spec.to_string()).unwrap(), spec);
+    }
+}
@@ -1,4 +1,13 @@
+use std::io::{self, ErrorKind, Read};
 use std::net:: {TcpStream, TcpListener} ;
+use std::time::Duration;
+use num_derive::FromPrimitive;
+
+pub mod faults;
+pub mod network;
+pub mod tls;
+
+use tls::{NetStream, TlsConfig};
 
 // log macro, works same as println macro
 // will print only in debug mode
@@ -12,27 +21,170 @@ macro_rules! log {
     };
 }
 
+#[derive(FromPrimitive, Copy, Clone, Debug, PartialEq)]
+pub enum Algo {
+    OddEvenTransposition, 
+    Sasaki, 
+    Triplet,
+    AsyncOddEven,   // no rounds, nodes exchange as soon as a neighbour's message arrives
+}
+
+#[derive(FromPrimitive, PartialEq, Debug, Clone, Copy)]
+pub enum PartialOrder {
+    LessThan,
+    GreaterThan,
+}
+
+impl Algo {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algo::OddEvenTransposition => "odd-even",
+            Algo::Sasaki               => "sasaki",
+            Algo::Triplet              => "triplet",
+            Algo::AsyncOddEven         => "async-odd-even",
+        }
+    }
+}
+
+impl PartialOrder {
+    pub fn from_name(name: &str) -> Option<PartialOrder> {
+        match name {
+            "asc"  => Some(PartialOrder::LessThan),
+            "desc" => Some(PartialOrder::GreaterThan),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PartialOrder::LessThan    => "asc",
+            PartialOrder::GreaterThan => "desc",
+        }
+    }
+}
+
+pub fn get_rounds (algo : Algo, no_nodes : u32) -> u32 {
+    // nothing to compare a single value with
+    if no_nodes < 2 {
+        return 0;
+    }
+    match algo {
+        Algo::OddEvenTransposition => no_nodes,
+        Algo::Sasaki               => no_nodes - 1,
+        Algo::Triplet              => no_nodes - 1,
+        Algo::AsyncOddEven         => 0,
+    }
+}
+
 pub enum CommFlags {
     // Sent by Distributor to Node
     Order,
+    Verdict,
+    Reset,    // pool : drop the neighbour links, the node sits out the next run
 
     // Sent by Node to Distributor
     Report,
     Finish,
+    Trace,
+    Vote,
+    Abort,    // a neighbour was lost, sorting could not finish
+    Idle,     // async : node ran out of work, carries its message counters
+    Status,   // async : reply to a Probe, carries its message counters
+
+    // Sent by Distributor to Node (async)
+    Probe,
+    Stop,
 
     // Sent from one node to another
     NeigbourConnect,
     Exchange,
+    Token,
+    Propose,  // async : left node offers its value to its right neighbour
+    Reply,    // async : value the right node hands back
+    Poke,     // async : right node's value changed, left node has to propose again
 }
 
+// bits of the options byte carried by an Order
+pub const OPT_TRACE      : u8 = 1;   // report the value held after every round
+pub const OPT_EARLY_VOTE : u8 = 2;   // vote to the distributor after every round to stop early
+pub const OPT_EARLY_WAVE : u8 = 4;   // detect termination with a wave along the line
+pub const OPT_POOL       : u8 = 8;   // stay connected after Finish and wait for the next Order
+pub const OPT_DUPLEX     : u8 = 16;  // one full-duplex connection per neighbour pair, made by the left node
+pub const OPT_CHECKSUM   : u8 = 32;  // CRC32 on every message between neighbours
+pub const OPT_RELIABLE   : u8 = 64;  // neighbour messages acknowledged, resent when lost, duplicates discarded
+
+// Layout of the messages between the distributor and the nodes, reported by every node
+// so binaries built apart are refused instead of misreading each other
+//     1 : counts, positions and rounds as u16
+//     2 : counts, positions and rounds as u32
+//     3 : Order names the algorithm, as registered, instead of numbering it
+//     4 : Order carries a run id, neighbours connect with their rank and the run id
+//     5 : run token in Report, in the neighbour connect and in every neighbour frame
+//     6 : OPT_CHECKSUM, Abort for a message that failed its checksum
+//     7 : OPT_RELIABLE, Finish counts the messages resent
+//     8 : Abort for a neighbour that never connected
+//     9 : Finish carries the time spent on TLS handshakes with the neighbours
+pub const PROTOCOL_VERSION : u8 = 9;
+
+// Sizes of the fixed size messages between the distributor and the nodes, flag included
+// Order : flag + name length + partial order + no.of nodes u32 + left port u16 + right port u16
+//         + position u32 + num i32 + options + crash round u32 + byzantine + run id u64, the name follows
+pub const ORDER_MSG   : usize = 33;
+// Report : flag + protocol version + port u16 + run token u64
+pub const REPORT_MSG  : usize = 12;
+// Finish : flag + num i32 + rounds used u32 + exchange msgs u32 + control msgs u32 + resent msgs u32
+//          + neighbour TLS handshakes u32 (µs)
+pub const FINISH_MSG  : usize = 25;
+// Abort : flag + reason + side + round u32 + num i32
+pub const ABORT_MSG   : usize = 11;
+// Idle / Status (async) : flag + is idle + sent u32 + received u32
+pub const COUNTER_MSG : usize = 10;
+
+// Environment variable the distributor hands its random run token to the nodes in.
+// Connections without the token are refused, so processes of other runs on the
+// same machine cannot get in
+pub const TOKEN_ENV : &str = "SORT_RUN_TOKEN";
+
+// how long connecting over TLS waits for the server to take part in the handshake,
+// a node only accepts its neighbours once it has its Order
+pub const TLS_HANDSHAKE_TIMEOUT : Duration = Duration::from_secs(30);
+
+// how long the async loops of the nodes and of the distributor sleep when nothing had arrived
+pub const IDLE_WAIT : Duration = Duration::from_micros(200);
+
 pub struct Utility;
 
 impl Utility {
 
-    // Connects to a socket server
-    pub fn connect_to_server (port: u16) -> TcpStream {
-        TcpStream::connect(format!("127.0.0.1:{}", port))
-            .expect(&format!("Failed to connect to 127.0.0.1:{}", port))
+    // Connects to a socket server, over TLS when given the config of the run.
+    // The handshake is done before returning
+    pub fn connect_to_server (port: u16, tls: Option<&TlsConfig>) -> NetStream {
+        Self::try_connect_to_server(port, tls)
+            .unwrap_or_else(|e| panic!("Failed to connect to 127.0.0.1:{} : {}", port, e))
+    }
+
+    // connect_to_server for a peer that may be gone, leaves the failure to the caller
+    pub fn try_connect_to_server (port: u16, tls: Option<&TlsConfig>) -> io::Result<NetStream> {
+        let stream = TcpStream::connect(format!("127.0.0.1:{}", port))?;
+        let Some(tls) = tls else {
+            return Ok(NetStream::plain(stream));
+        };
+        let stream = NetStream::client(stream, tls)?;
+        stream.set_read_timeout(Some(TLS_HANDSHAKE_TIMEOUT))?;
+        stream.handshake()?;
+        stream.set_read_timeout(None)?;
+        Ok(stream)
+    }
+
+    // Accepts a connection on a server made by create_server, over TLS when given
+    // the config of the run. The handshake is left to the first read or to
+    // NetStream::handshake, so a silent peer cannot hold up the caller here
+    pub fn accept(listener: &TcpListener, tls: Option<&TlsConfig>) -> io::Result<NetStream> {
+        let (stream, _) = listener.accept()?;
+        match tls {
+            Some(tls) => NetStream::server(stream, tls),
+            None => Ok(NetStream::plain(stream)),
+        }
     }
 
     // create sa socket server
@@ -47,3 +199,82 @@ impl Utility {
         (listener, port_num)
     }
 }
+
+// Reads fixed size messages from a non-blocking stream, keeping partial reads
+// until the whole message has arrived
+pub struct MsgReader {
+    buffer  : Vec<u8>,
+    size_of : Box<dyn Fn(u8) -> usize>,   // size of a message given its flag
+}
+
+impl MsgReader {
+    // every message has the same size
+    pub fn new(size: usize) -> MsgReader {
+        Self::by_flag(move |_| size)
+    }
+
+    // the size of a message depends on its flag (first byte)
+    pub fn by_flag(size_of: impl Fn(u8) -> usize + 'static) -> MsgReader {
+        MsgReader { buffer: Vec::new(), size_of: Box::new(size_of) }
+    }
+
+    // part of a message has arrived, the rest is still due
+    pub fn is_partial(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+
+    // returns the next complete message, None if it has not fully arrived yet
+    pub fn poll(&mut self, stream: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+        let size = match self.buffer.first() {
+            Some(flag) => (self.size_of)(*flag),
+            None => 1,
+        };
+        let mut chunk = vec![0u8; size - self.buffer.len()];
+        match stream.read(&mut chunk) {
+            Ok(0) => return Err(io::Error::new(ErrorKind::UnexpectedEof, "Peer disconnected")),
+            Ok(bytes_read) => self.buffer.extend_from_slice(&chunk[..bytes_read]),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => (),
+            Err(e) => return Err(e),
+        }
+
+        if !self.buffer.is_empty() && self.buffer.len() == (self.size_of)(self.buffer[0]) {
+            let msg = self.buffer.clone();
+            self.buffer.clear();
+            Ok(Some(msg))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGOS : [Algo; 4] = [Algo::OddEvenTransposition, Algo::Sasaki, Algo::Triplet, Algo::AsyncOddEven];
+
+    #[test]
+    fn single_node_runs_no_rounds() {
+        for algo in ALGOS {
+            assert_eq!(get_rounds(algo, 0), 0);
+            assert_eq!(get_rounds(algo, 1), 0);
+        }
+    }
+
+    #[test]
+    fn rounds_of_a_line() {
+        assert_eq!(get_rounds(Algo::OddEvenTransposition, 2), 2);
+        assert_eq!(get_rounds(Algo::OddEvenTransposition, 7), 7);
+        assert_eq!(get_rounds(Algo::Sasaki, 2), 1);
+        assert_eq!(get_rounds(Algo::Triplet, 7), 6);
+        assert_eq!(get_rounds(Algo::AsyncOddEven, 7), 0);
+    }
+
+    #[test]
+    fn orders_by_name() {
+        for partial_order in [PartialOrder::LessThan, PartialOrder::GreaterThan] {
+            assert_eq!(PartialOrder::from_name(partial_order.name()), Some(partial_order));
+        }
+        assert_eq!(PartialOrder::from_name("up"), None);
+    }
+}
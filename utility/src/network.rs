@@ -0,0 +1,157 @@
+use crate::Algo;
+
+// Which neighbours a node exchanges with in a given round.
+// Both the node's algorithms and the distributor's diagram generator
+// use these, so the drawn network is exactly the one the nodes execute.
+// Positions are 1-based global positions (glb_pos), rounds are 0-based.
+
+// Odd-Even : odd indexed nodes pair with their right neighbour in odd rounds
+// (round 0 is the first odd round), even indexed nodes in even rounds
+pub fn odd_even_pairs_right(glb_pos: u32, round: u32) -> bool {
+    (glb_pos as u64 + round as u64) % 2 == 1
+}
+
+// Triplet : role of a node in a round
+// 0 -> left member of a triplet, exchanges with its right neighbour
+// 1 -> centre of a triplet, sorts its value with both neighbours
+// 2 -> right member of a triplet, exchanges with its left neighbour
+// Round 0 starts with whole triplets from the left end, except when no_nodes % 3 == 1 where
+// the left end pairs with its right neighbour alone. Any other phase needs more than n - 1
+// rounds for some n, e.g. (1,2) then (2,3) leaves 3,2,1 unsorted
+pub fn triplet_role(glb_pos: u32, round: u32, no_nodes: u32) -> u8 {
+    let phase = if no_nodes % 3 == 1 { 0 } else { 2 };
+    ((glb_pos as u64 + round as u64 + phase) % 3) as u8
+}
+
+// No. of consecutive rounds without any change after which the line can stop early.
+// The pairing repeats with this period, so once every kind of round left all the 
+// values untouched no later round can change them either
+pub fn quiet_window(algo: Algo) -> u32 {
+    match algo {
+        Algo::OddEvenTransposition => 2,
+        Algo::Sasaki               => 1,
+        Algo::Triplet              => 3,
+        Algo::AsyncOddEven         => 1,
+    }
+}
+
+// A group of consecutive positions (first..=last) compared together in a round
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparator {
+    pub first : u32,
+    pub last  : u32,
+}
+
+// Comparators executed in a round on a line of no_nodes nodes
+pub fn comparators(algo: Algo, no_nodes: u32, round: u32) -> Vec<Comparator> {
+    let mut groups = Vec::new();
+
+    for glb_pos in 1..=no_nodes {
+        match algo {
+            Algo::OddEvenTransposition => {
+                if odd_even_pairs_right(glb_pos, round) && glb_pos < no_nodes {
+                    groups.push(Comparator { first: glb_pos, last: glb_pos + 1 });
+                }
+            },
+
+            // every node exchanges with both of its neighbours every round
+            Algo::Sasaki => {
+                if glb_pos < no_nodes {
+                    groups.push(Comparator { first: glb_pos, last: glb_pos + 1 });
+                }
+            },
+
+            Algo::Triplet => {
+                if triplet_role(glb_pos, round, no_nodes) == 1 {
+                    let first = if glb_pos > 1 { glb_pos - 1 } else { glb_pos };
+                    let last  = if glb_pos < no_nodes { glb_pos + 1 } else { glb_pos };
+                    if first != last {
+                        groups.push(Comparator { first, last });
+                    }
+                }
+            },
+
+            // has no rounds, pairs compare whenever their messages arrive
+            Algo::AsyncOddEven => (),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_rounds;
+
+    fn roles(no_nodes: u32, round: u32) -> Vec<u8> {
+        (1..=no_nodes).map(|glb_pos| triplet_role(glb_pos, round, no_nodes)).collect()
+    }
+
+    fn pairs(comparators: &[Comparator]) -> Vec<(u32, u32)> {
+        comparators.iter().map(|comparator| (comparator.first, comparator.last)).collect()
+    }
+
+    #[test]
+    fn triplet_starts_with_whole_triplets_from_the_left_end() {
+        assert_eq!(roles(2, 0), [0, 1]);
+        assert_eq!(roles(3, 0), [0, 1, 2]);
+        assert_eq!(roles(5, 0), [0, 1, 2, 0, 1]);
+        assert_eq!(roles(6, 0), [0, 1, 2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn triplet_pairs_the_left_end_alone_when_one_node_is_left_over() {
+        assert_eq!(roles(1, 0), [1]);
+        assert_eq!(roles(4, 0), [1, 2, 0, 1]);
+        assert_eq!(roles(7, 0), [1, 2, 0, 1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn triplet_roles_shift_by_one_every_round() {
+        for no_nodes in 1..=9 {
+            for round in 0..no_nodes {
+                for (role, next) in roles(no_nodes, round).into_iter().zip(roles(no_nodes, round + 1)) {
+                    assert_eq!(next, (role + 1) % 3, "n={} round {}", no_nodes, round);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn comparators_of_a_round() {
+        assert_eq!(pairs(&comparators(Algo::OddEvenTransposition, 4, 0)), [(1, 2), (3, 4)]);
+        assert_eq!(pairs(&comparators(Algo::OddEvenTransposition, 4, 1)), [(2, 3)]);
+        assert_eq!(pairs(&comparators(Algo::Sasaki, 3, 5)), [(1, 2), (2, 3)]);
+        assert_eq!(pairs(&comparators(Algo::Triplet, 3, 0)), [(1, 3)]);
+        assert_eq!(pairs(&comparators(Algo::Triplet, 4, 0)), [(1, 2), (3, 4)]);
+        assert_eq!(pairs(&comparators(Algo::Triplet, 4, 1)), [(2, 4)]);
+        assert_eq!(pairs(&comparators(Algo::Triplet, 5, 0)), [(1, 3), (4, 5)]);
+        assert!(comparators(Algo::AsyncOddEven, 4, 0).is_empty());
+    }
+
+    #[test]
+    fn a_single_node_compares_nothing() {
+        for algo in [Algo::OddEvenTransposition, Algo::Sasaki, Algo::Triplet, Algo::AsyncOddEven] {
+            assert!(comparators(algo, 1, 0).is_empty());
+        }
+    }
+
+    // odd-even and triplet compare every position at most once a round, within the line
+    #[test]
+    fn comparators_do_not_overlap() {
+        for algo in [Algo::OddEvenTransposition, Algo::Triplet] {
+            for no_nodes in 1..=9 {
+                for round in 0..get_rounds(algo, no_nodes) {
+                    let mut used = vec![false; no_nodes as usize + 1];
+                    for Comparator { first, last } in comparators(algo, no_nodes, round) {
+                        assert!(1 <= first && first < last && last <= no_nodes);
+                        for glb_pos in first..=last {
+                            assert!(!used[glb_pos as usize], "{:?} n={} round {}", algo, no_nodes, round);
+                            used[glb_pos as usize] = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, TcpStream};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use rustls::crypto::ring;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection, StreamOwned};
+
+// Optional TLS on the connections between the distributor and the nodes and between
+// neighbours. The distributor makes a CA for the run, signs one certificate with it and
+// hands the nodes the bundle : CA certificate, certificate and its key, in PEM.
+// Every side presents the certificate and only trusts the CA, so both ends of a
+// connection know the other one belongs to the run. Nothing is fetched, it works offline.
+
+// Environment variable the distributor hands the PEM bundle over in
+pub const TLS_ENV : &str = "SORT_RUN_TLS";
+
+// Both sides of the TLS sessions of a run
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    client : Arc<ClientConfig>,
+    server : Arc<ServerConfig>,
+}
+
+impl TlsConfig {
+    // from the bundle of the run : CA certificate, certificate, private key
+    pub fn from_pem(bundle: &str) -> Result<TlsConfig, String> {
+        let certs = CertificateDer::pem_slice_iter(bundle.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid certificate : {}", e))?;
+        let [ca, cert] = <[CertificateDer; 2]>::try_from(certs)
+            .map_err(|certs| format!("Expected the CA and one certificate, got {} certificates", certs.len()))?;
+        let key = PrivateKeyDer::from_pem_slice(bundle.as_bytes())
+            .map_err(|e| format!("Invalid private key : {}", e))?;
+
+        let mut roots = RootCertStore::empty();
+        roots.add(ca).map_err(|e| format!("Invalid CA certificate : {}", e))?;
+        let roots = Arc::new(roots);
+        let provider = Arc::new(ring::default_provider());
+
+        let client = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| builder.with_root_certificates(roots.clone())
+                                       .with_client_auth_cert(vec![cert.clone()], key.clone_key()))
+            .map_err(|e| format!("Invalid client config : {}", e))?;
+
+        let verifier = WebPkiClientVerifier::builder_with_provider(roots, provider.clone()).build()
+            .map_err(|e| format!("Invalid client verifier : {}", e))?;
+        let mut server = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| builder.with_client_cert_verifier(verifier)
+                                       .with_single_cert(vec![cert], key))
+            .map_err(|e| format!("Invalid server config : {}", e))?;
+        // sessions are never resumed, a ticket would only sit unread on one-way links
+        server.send_tls13_tickets = 0;
+
+        Ok(TlsConfig { client: Arc::new(client), server: Arc::new(server) })
+    }
+}
+
+// A connection to the distributor or a neighbour, in the clear or over TLS.
+// Clones share the connection like TcpStream::try_clone, a TLS session is used
+// by one clone at a time
+#[derive(Debug)]
+pub struct NetStream {
+    inner : Inner,
+}
+
+#[derive(Debug)]
+enum Inner {
+    Plain(TcpStream),
+    Tls(Arc<Mutex<Session>>),
+}
+
+#[derive(Debug)]
+enum Session {
+    Client(StreamOwned<ClientConnection, TcpStream>),
+    Server(StreamOwned<ServerConnection, TcpStream>),
+}
+
+fn tls_error(e: rustls::Error) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, e)
+}
+
+impl NetStream {
+    pub fn plain(stream: TcpStream) -> NetStream {
+        NetStream { inner: Inner::Plain(stream) }
+    }
+
+    // client end of a session, the other end is the server at 127.0.0.1
+    pub fn client(stream: TcpStream, tls: &TlsConfig) -> io::Result<NetStream> {
+        let name = ServerName::from(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        let conn = ClientConnection::new(tls.client.clone(), name).map_err(tls_error)?;
+        Ok(Self::session(Session::Client(StreamOwned::new(conn, stream))))
+    }
+
+    pub fn server(stream: TcpStream, tls: &TlsConfig) -> io::Result<NetStream> {
+        let conn = ServerConnection::new(tls.server.clone()).map_err(tls_error)?;
+        Ok(Self::session(Session::Server(StreamOwned::new(conn, stream))))
+    }
+
+    fn session(session: Session) -> NetStream {
+        NetStream { inner: Inner::Tls(Arc::new(Mutex::new(session))) }
+    }
+
+    fn lock(session: &Mutex<Session>) -> MutexGuard<'_, Session> {
+        session.lock().expect("TLS session poisoned")
+    }
+
+    // completes the TLS handshake now instead of on the first read or write,
+    // nothing to do in the clear
+    pub fn handshake(&self) -> io::Result<()> {
+        let Inner::Tls(session) = &self.inner else {
+            return Ok(());
+        };
+        // the client is done before its last flight is written, so that is sent as well
+        match &mut *Self::lock(session) {
+            Session::Client(stream) => while stream.conn.is_handshaking() || stream.conn.wants_write() {
+                stream.conn.complete_io(&mut stream.sock)?;
+            },
+            Session::Server(stream) => while stream.conn.is_handshaking() || stream.conn.wants_write() {
+                stream.conn.complete_io(&mut stream.sock)?;
+            },
+        }
+        Ok(())
+    }
+
+    pub fn try_clone(&self) -> io::Result<NetStream> {
+        let inner = match &self.inner {
+            Inner::Plain(stream) => Inner::Plain(stream.try_clone()?),
+            Inner::Tls(session)  => Inner::Tls(session.clone()),
+        };
+        Ok(NetStream { inner })
+    }
+
+    // runs `f` on the socket underneath
+    fn with_socket<T>(&self, f: impl FnOnce(&TcpStream) -> T) -> T {
+        match &self.inner {
+            Inner::Plain(stream) => f(stream),
+            Inner::Tls(session) => match &*Self::lock(session) {
+                Session::Client(stream) => f(&stream.sock),
+                Session::Server(stream) => f(&stream.sock),
+            },
+        }
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.with_socket(|stream| stream.set_nonblocking(nonblocking))
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.with_socket(|stream| stream.set_read_timeout(timeout))
+    }
+}
+
+impl Read for NetStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            Inner::Plain(stream) => stream.read(buf),
+            Inner::Tls(session) => match &mut *Self::lock(session) {
+                Session::Client(stream) => stream.read(buf),
+                Session::Server(stream) => stream.read(buf),
+            },
+        }
+    }
+}
+
+impl Write for NetStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            Inner::Plain(stream) => stream.write(buf),
+            Inner::Tls(session) => match &mut *Self::lock(session) {
+                Session::Client(stream) => stream.write(buf),
+                Session::Server(stream) => stream.write(buf),
+            },
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::Plain(stream) => stream.flush(),
+            Inner::Tls(session) => match &mut *Self::lock(session) {
+                Session::Client(stream) => stream.flush(),
+                Session::Server(stream) => stream.flush(),
+            },
+        }
+    }
+}